@@ -1,34 +1,154 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
 use color_eyre::Result;
+use color_eyre::eyre::eyre;
 use crossbeam_channel::{Receiver, Sender};
+use crossterm::event::{MouseEvent, MouseEventKind};
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Constraint, Direction, Layout, Position, Rect};
+use ratatui::style::Style;
 use ratatui::widgets::WidgetRef;
 use rodio::Source;
 use rusqlite::Connection;
-use std::time::Duration;
+use uuid::Uuid;
 
+use crate::backend::output_devices;
+use crate::commands::{self, Action, Change, PlaylistAction};
 use crate::components::ComponentCommand;
+use crate::components::cover_art::GraphicsProtocol;
+use crate::components::playlist::Command as PlaylistCommand;
+use crate::components::queue::QueueEntry;
+use crate::components::tracklist::Command as TracklistCommand;
 use crate::components::{
-    Component, PlayerControlsComponent, PlaylistComponent, TracklistComponent,
+    Binding, BrowserComponent, CommandLineComponent, CommandPaletteComponent, Component,
+    ConfirmComponent, CoverArtComponent, DevicePickerComponent, HelpComponent, InputComponent,
+    LyricsComponent, MessageComponent, PlayerControlsComponent, PlaylistComponent, PopupRequest,
+    QueueComponent, StatsComponent, TagEditorComponent, TracklistComponent,
 };
-use crate::config::Config;
+use crate::config::{Config, ControlsPosition, Panel};
 use crate::current_track::CurrentTrack;
+use crate::db::{self, Play, Session};
 use crate::event::{AudioMessage, Command as AudioCommand, EventState, Key};
-use crate::io::{add_metadata, get_files};
+use crate::io;
+use crate::library;
+use crate::models::{PlayContext, Playlist, Track, TrackTags};
+use crate::playlist_file::{self, PlaylistFormat};
+use crate::queue::{Queue, Repeat};
+use crate::stats::{ListeningStats, Period};
+use crate::utils::fuzzy_score;
+
+const DEFAULT_VOLUME: f32 = 0.05;
+const VOLUME_STEP: f32 = 0.01;
+const SEEK_STEP: Duration = Duration::from_secs(5);
+const CONTROLS_HEIGHT: u16 = 7;
+/// Percent of the main area a pane can't be shrunk below.
+const MIN_PANE_WIDTH: u16 = 10;
 
-pub enum Focus {
-    Tracklist,
+/// Popups drawn over the main area, each kind at most once. Each is backed
+/// by a single component, so requests for [`Popup::Confirm`] and
+/// [`Popup::Input`] made while one is open wait in [`App::pending_popups`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Popup {
     Playlist,
+    DevicePicker,
+    TagEditor,
+    CommandLine,
+    CommandPalette,
+    Help,
+    Confirm,
+    Input,
+    Message,
+}
+
+impl Popup {
+    fn name(self) -> &'static str {
+        match self {
+            Popup::Playlist => "Playlists",
+            Popup::DevicePicker => "Audio devices",
+            Popup::TagEditor => "Tag editor",
+            Popup::CommandLine => "Command line",
+            Popup::CommandPalette => "Command palette",
+            Popup::Help => "Help",
+            Popup::Confirm => "Confirmation",
+            Popup::Input => "Input",
+            Popup::Message => "Message",
+        }
+    }
+}
+
+/// Popup showing `request`.
+fn request_popup(request: &PopupRequest) -> Popup {
+    match request {
+        PopupRequest::Confirm { .. } => Popup::Confirm,
+        PopupRequest::Input { .. } => Popup::Input,
+    }
+}
+
+/// Player state reported to scripts in headless mode.
+pub struct Status {
+    pub track: Option<Uuid>,
+    pub position: Duration,
+    pub duration: Duration,
+    pub paused: bool,
+    pub volume: f32,
+    pub shuffle: bool,
+    pub repeat: Repeat,
 }
 
 pub struct App {
     tracklist: TracklistComponent,
+    browser: BrowserComponent,
     playlist: PlaylistComponent,
     player_controls: PlayerControlsComponent,
+    cover_art: CoverArtComponent,
+    device_picker: DevicePickerComponent,
+    stats: StatsComponent,
+    tag_editor: TagEditorComponent,
+    lyrics: LyricsComponent,
+    queue_view: QueueComponent,
+    command_line: CommandLineComponent,
+    command_palette: CommandPaletteComponent,
+    help: HelpComponent,
+    confirm: ConfirmComponent,
+    input: InputComponent,
+    message: MessageComponent,
 
+    library: Vec<Track>,
+    playlists: Vec<Playlist>,
+    queue: Queue,
     current_track: Option<CurrentTrack>,
+    position: Duration,
+    paused: bool,
+    /// Underrun message is shown until playback moves again.
+    underrun: bool,
+    volume: f32,
+    last_session_save: Instant,
+    /// Tags the tracks of the last tag edit had before it.
+    last_tag_edit: Vec<(Uuid, TrackTags)>,
 
-    focus: Focus,
+    focus: Panel,
+    /// Panel each pane of the layout shows.
+    shown: Vec<Panel>,
+    /// Width of each pane, in percent of the main area while all are
+    /// visible.
+    pane_widths: Vec<u16>,
+    /// Whole terminal as of the last frame, panes collapse by its width.
+    area: Cell<Rect>,
+    /// Open popups, the last one is on top and gets input first.
+    popups: Vec<Popup>,
+    /// Popup requests waiting for the open popup of their kind to close,
+    /// opening them right away would replace what it asks.
+    pending_popups: VecDeque<PopupRequest>,
+    /// Set by the quit command.
+    quit: bool,
+    /// Running without a terminal, see [`App::set_headless`].
+    headless: bool,
+    /// Panels drawn in the last frame, later ones on top, for focusing
+    /// with the mouse.
+    panels: RefCell<Vec<(Rect, Panel)>>,
     sqlite: Connection,
 
     audio_tx: Sender<AudioCommand>,
@@ -37,109 +157,1824 @@ pub struct App {
     pub config: Config,
 }
 
+/// Restyles the outermost cells of `area`, where panels draw their border.
+/// Keeps focus out of components, they draw the unfocused border.
+fn style_border(area: Rect, buf: &mut Buffer, style: Style) {
+    if area.is_empty() {
+        return;
+    }
+    let edges = (area.left()..area.right())
+        .flat_map(|x| [(x, area.top()), (x, area.bottom() - 1)])
+        .chain((area.top()..area.bottom()).flat_map(|y| [(area.left(), y), (area.right() - 1, y)]));
+    for pos in edges {
+        if let Some(cell) = buf.cell_mut(pos) {
+            cell.set_style(style);
+        }
+    }
+}
+
 impl App {
+    /// Picks up where the last session left off.
     pub fn new(audio_tx: Sender<AudioCommand>, config: Config, sqlite: Connection) -> Result<Self> {
-        let paths = get_files(&config.audio_dir, "mp3")?;
-        let tracks = add_metadata(paths);
+        let mut app = Self::without_session(audio_tx, config, sqlite)?;
+        app.restore_session()?;
+        app.update_controls();
+        app.update_queue_view();
+        Ok(app)
+    }
+
+    /// Starts with an empty queue and nothing playing, for playing tracks
+    /// given on the command line without disturbing the saved session.
+    pub fn without_session(
+        audio_tx: Sender<AudioCommand>,
+        config: Config,
+        sqlite: Connection,
+    ) -> Result<Self> {
+        db::init(&sqlite)?;
+
+        let tracks = library::load(&config.audio_dir, &sqlite)?;
+        let playlists = db::playlists(&sqlite)?;
 
         let (app_cmd_tx, app_cmd_rx) = crossbeam_channel::bounded(256);
 
-        Ok(App {
+        let mut app = App {
             tracklist: TracklistComponent::new(
-                tracks,
+                tracks.clone(),
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            browser: BrowserComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            playlist: PlaylistComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            player_controls: PlayerControlsComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            cover_art: CoverArtComponent::new(
+                config
+                    .graphics_protocol
+                    .unwrap_or_else(GraphicsProtocol::detect),
+                config.theme.clone(),
+            ),
+            device_picker: DevicePickerComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            stats: StatsComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            tag_editor: TagEditorComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            lyrics: LyricsComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            queue_view: QueueComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            command_line: CommandLineComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            command_palette: CommandPaletteComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            help: HelpComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            confirm: ConfirmComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            input: InputComponent::new(
+                config.key_config.clone(),
+                config.theme.clone(),
+                app_cmd_tx.clone(),
+            ),
+            message: MessageComponent::new(
                 config.key_config.clone(),
+                config.theme.clone(),
                 app_cmd_tx.clone(),
             ),
-            playlist: PlaylistComponent {},
-            player_controls: PlayerControlsComponent::new(),
+            library: tracks,
+            playlists,
+            queue: Queue::default(),
             current_track: None,
-            focus: Focus::Tracklist,
+            position: Duration::ZERO,
+            paused: false,
+            underrun: false,
+            volume: DEFAULT_VOLUME,
+            last_session_save: Instant::now(),
+            last_tag_edit: vec![],
+            focus: config.layout.panes[0].panels[0],
+            shown: config.layout.panes.iter().map(|p| p.panels[0]).collect(),
+            pane_widths: {
+                let total = config
+                    .layout
+                    .panes
+                    .iter()
+                    .map(|p| p.width as u32)
+                    .sum::<u32>();
+                config
+                    .layout
+                    .panes
+                    .iter()
+                    .map(|p| (p.width as u32 * 100 / total).max(1) as u16)
+                    .collect()
+            },
+            area: Cell::new(Rect::default()),
+            popups: vec![],
+            pending_popups: VecDeque::new(),
+            quit: false,
+            headless: false,
+            panels: RefCell::new(vec![]),
             sqlite,
             audio_tx,
             widget_cmd_rx: app_cmd_rx,
             config,
-        })
+        };
+
+        app.playlist.set_playlists(&app.playlists);
+        app.browser.set_library(&app.library);
+        app.tracklist.set_follow(app.config.follow_playback);
+        if app.shown.contains(&Panel::Stats) {
+            app.show_stats(app.stats.period())?;
+        }
+        app.update_controls();
+        app.update_queue_view();
+
+        Ok(app)
     }
 
     pub fn render(&self, area: Rect, buf: &mut Buffer) {
-        let [main_area, controls_area] = Layout::new(
-            Direction::Vertical,
-            [Constraint::Fill(1), Constraint::Length(5)],
+        self.area.set(area);
+        let layout = &self.config.layout;
+
+        let [main_area, controls_area] = match layout.controls {
+            ControlsPosition::Top => {
+                let [controls, main] = Layout::new(
+                    Direction::Vertical,
+                    [Constraint::Length(CONTROLS_HEIGHT), Constraint::Fill(1)],
+                )
+                .areas(area);
+                [main, controls]
+            }
+            ControlsPosition::Bottom => Layout::new(
+                Direction::Vertical,
+                [Constraint::Fill(1), Constraint::Length(CONTROLS_HEIGHT)],
+            )
+            .areas(area),
+        };
+
+        // Cells are about twice as tall as wide, this keeps covers square.
+        let cover_width = if area.width < layout.cover_min_terminal_width {
+            0
+        } else {
+            CONTROLS_HEIGHT * 2
+        };
+        let [cover_area, controls_area] = Layout::new(
+            Direction::Horizontal,
+            [Constraint::Length(cover_width), Constraint::Fill(1)],
         )
-        .areas(area);
+        .areas(controls_area);
 
+        self.cover_art.render_ref(cover_area, buf);
         self.player_controls.render_ref(controls_area, buf);
 
-        match self.focus {
-            Focus::Tracklist => self.tracklist.render_ref(main_area, buf),
-            Focus::Playlist => unimplemented!(),
+        let mut panels = self.panels.borrow_mut();
+        panels.clear();
+
+        let visible = self.visible_panes(area.width);
+        let pane_areas = Layout::horizontal(
+            visible
+                .iter()
+                .map(|&(pane, _)| Constraint::Fill(self.pane_widths[pane])),
+        )
+        .split(main_area);
+
+        for (&(_, panel), &pane_area) in visible.iter().zip(pane_areas.iter()) {
+            panels.push((pane_area, panel));
+            match panel {
+                Panel::Tracklist => self.tracklist.render_ref(pane_area, buf),
+                Panel::Browser => self.browser.render_ref(pane_area, buf),
+                Panel::Stats => self.stats.render_ref(pane_area, buf),
+                Panel::Lyrics => self.lyrics.render_ref(pane_area, buf),
+                Panel::Queue => self.queue_view.render_ref(pane_area, buf),
+            }
+            if panel == self.focus && self.popups.is_empty() {
+                style_border(pane_area, buf, self.config.theme.focused_border);
+            }
+        }
+
+        for popup in self.popups.iter() {
+            self.popup_widget(*popup).render_ref(main_area, buf);
         }
     }
 
+    /// Image escape sequence to write to the terminal after a frame is drawn.
+    pub fn graphics(&self) -> Option<(Rect, String)> {
+        self.cover_art.graphics()
+    }
+
+    /// Top-most popup gets keys first, without popups the focused view
+    /// does.
     pub fn event(&mut self, key: Key) -> Result<EventState> {
-        let res = self.component_event(key);
+        let mut res = match self.popups.last() {
+            Some(&popup) => self.popup_event(popup, key),
+            None => self.component_event(key),
+        };
+        if matches!(res, Ok(EventState::NotConsumed)) {
+            res = self.player_controls.event(key);
+        }
+        if matches!(res, Ok(EventState::NotConsumed)) {
+            res = self.global_event(key);
+        }
         self.drain_commands()?;
+        self.update_controls();
+        self.update_queue_view();
+        res
+    }
+
+    /// Whether the quit command was run.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Runs a command line command without going through the UI, errors
+    /// are returned instead of shown.
+    pub fn command(&mut self, line: &str) -> Result<(), String> {
+        let res = self.execute(line);
+        self.drain_commands().map_err(|e| e.to_string())?;
+        self.update_controls();
+        self.update_queue_view();
         res
     }
 
+    /// Plays `tracks` in order, replacing the context.
+    pub fn play_tracks(&mut self, tracks: Vec<Uuid>) -> Result<()> {
+        if let Some(uuid) = self.queue.play_from(tracks, 0) {
+            self.play_track(uuid, PlayContext::Library)?;
+        }
+        self.update_controls();
+        Ok(())
+    }
+
+    pub fn status(&self) -> Status {
+        Status {
+            track: self.current_track.as_ref().map(|t| t.uuid),
+            position: self.position,
+            duration: self.player_controls.duration,
+            paused: self.paused,
+            volume: self.volume,
+            shuffle: self.queue.shuffle(),
+            repeat: self.queue.repeat(),
+        }
+    }
+
+    /// Nobody sees the tracklist, so commands can't act on its selection.
+    pub fn set_headless(&mut self, headless: bool) {
+        self.headless = headless;
+    }
+
+    pub fn library(&self) -> &[Track] {
+        &self.library
+    }
+
+    pub fn mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        let res = match self.popups.last() {
+            Some(&popup) => self.popup_component(popup).mouse(mouse)?,
+            None => {
+                if let MouseEventKind::Down(_) = mouse.kind
+                    && let Some(focus) = self.panel_at(mouse.column, mouse.row)
+                {
+                    self.focus = focus;
+                }
+                self.component_mouse(mouse)?
+            }
+        };
+
+        if !res.is_consumed() {
+            self.player_controls.mouse(mouse)?;
+        }
+        self.drain_commands()?;
+        self.update_controls();
+        self.update_queue_view();
+        Ok(())
+    }
+
+    /// Terminal clears the screen on resize, images drawn outside ratatui
+    /// have to be sent again.
+    pub fn resize(&mut self) {
+        self.cover_art.invalidate();
+    }
+
     pub fn tick(&mut self) -> Result<()> {
-        self.audio_tx.send(AudioCommand::SendState)?;
+        if self.last_session_save.elapsed() >= self.config.session_save_interval {
+            self.save_session()?;
+        }
+
         Ok(())
     }
 
-    pub fn audio(&mut self, audio_message: AudioMessage) {
+    /// Returns whether anything shown changed. Positions arrive several times
+    /// a second but the shown one only changes every second, redrawing for
+    /// each would keep the terminal busy however slow ticks are.
+    pub fn audio(&mut self, audio_message: AudioMessage) -> Result<bool> {
+        let position_only = matches!(audio_message, AudioMessage::Position { .. });
+        let shown = self.shown_position();
+
         match audio_message {
             AudioMessage::EndOfTrack => {
-                self.player_controls.progress = 0;
+                let ended = self.current_track.as_ref().map(|t| (t.uuid, t.context));
+                if let Some((uuid, _)) = ended {
+                    db::clear_resume_position(&self.sqlite, uuid)?;
+                }
+                self.finish_play(false)?;
+
                 self.player_controls.name = None;
-                self.current_track = None;
-            }
-            AudioMessage::State(state) => {
-                let progress = if let Some(current_track) = self.current_track.as_ref() {
-                    (state.pos.as_secs_f32() / current_track.total_duration.as_secs_f32() * 100.0)
-                        .ceil() as u16
-                } else {
-                    0
+                self.player_controls.set_track(None);
+                self.set_playing(None);
+                self.cover_art.set_track(None);
+                self.lyrics.set_lyrics(None);
+                self.position = Duration::ZERO;
+
+                let next = match self.queue.repeat() {
+                    Repeat::One => ended,
+                    _ => self.next_track(),
                 };
+                if let Some((uuid, context)) = next {
+                    self.play_track(uuid, context)?;
+                }
+            }
+            AudioMessage::Started { paused } => {
+                self.paused = paused;
+                self.player_controls.error = None;
+                if let Some(current_track) = self.current_track.as_mut() {
+                    current_track.audio_started = true;
+                }
+            }
+            AudioMessage::Paused => self.paused = true,
+            AudioMessage::Resumed => self.paused = false,
+            AudioMessage::Position { pos, played } => {
+                if let Some(current_track) = self.current_track.as_mut()
+                    && current_track.audio_started
+                {
+                    current_track.listened = played;
+                }
+                self.position = pos;
+                self.lyrics.set_position(pos);
 
-                self.player_controls.progress = progress;
+                if self.underrun {
+                    self.underrun = false;
+                    self.player_controls.error = None;
+                }
+            }
+            AudioMessage::VolumeChanged(volume) => self.volume = volume,
+            AudioMessage::DeviceChanged(name) => {
+                self.player_controls.error = None;
+                self.device_picker.set_current(name);
+            }
+            AudioMessage::Underrun => {
+                self.underrun = true;
+                self.player_controls.error = Some("Audio output underrun".to_string());
+            }
+            AudioMessage::Error(error) => {
+                self.player_controls.error = Some(error);
             }
-            AudioMessage::Noop => {}
         }
+
+        self.update_controls();
+        self.update_queue_view();
+        Ok(!position_only || self.shown_position() != shown)
     }
 
-    fn component_event(&mut self, key: Key) -> Result<EventState> {
+    /// What position updates can change on screen.
+    fn shown_position(&self) -> (u64, Option<usize>, bool) {
+        (
+            self.position.as_secs(),
+            self.lyrics.current(),
+            self.underrun,
+        )
+    }
+
+    /// Writes current player state to the database so it can be restored on
+    /// next launch.
+    pub fn save_session(&mut self) -> Result<()> {
+        self.last_session_save = Instant::now();
+        self.save_resume_position()?;
+
+        db::save_session(
+            &self.sqlite,
+            &Session {
+                track: self.current_track.as_ref().map(|t| t.uuid),
+                pos: self.position,
+                volume: self.volume,
+                shuffle: self.queue.shuffle(),
+                manual_queue: self.queue.manual.clone(),
+                context: self.queue.context().to_vec(),
+                context_pos: self.queue.pos(),
+                repeat: self.queue.repeat(),
+            },
+        )
+    }
+
+    /// Writes the track being played into play history as far as it was
+    /// listened, for when the app exits. Session should be saved first, it
+    /// stops tracking the track.
+    pub fn record_unfinished_play(&mut self) -> Result<()> {
+        self.finish_play(false)
+    }
+
+    fn restore_session(&mut self) -> Result<()> {
+        let Some(session) = db::load_session(&self.sqlite)? else {
+            return Ok(());
+        };
+
+        self.volume = session.volume;
+        _ = self.audio_tx.send(AudioCommand::SetVolume(self.volume));
+
+        self.queue = Queue::restore(
+            session.manual_queue,
+            session.context,
+            session.context_pos,
+            session.shuffle,
+            session.repeat,
+        );
+
+        if let Some(track) = session.track.and_then(|uuid| self.track(uuid)).cloned() {
+            self.start(&track, session.pos, true, PlayContext::Library)?;
+        }
+
+        Ok(())
+    }
+
+    fn focused_component(&mut self) -> &mut dyn Component {
         match self.focus {
-            Focus::Tracklist => self.tracklist.event(key),
-            Focus::Playlist => unimplemented!(),
+            Panel::Tracklist => &mut self.tracklist,
+            Panel::Browser => &mut self.browser,
+            Panel::Stats => &mut self.stats,
+            Panel::Lyrics => &mut self.lyrics,
+            Panel::Queue => &mut self.queue_view,
         }
     }
 
+    fn component_event(&mut self, key: Key) -> Result<EventState> {
+        self.focused_component().event(key)
+    }
+
+    fn component_mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        self.focused_component().mouse(mouse)
+    }
+
+    fn popup_component(&mut self, popup: Popup) -> &mut dyn Component {
+        match popup {
+            Popup::Playlist => &mut self.playlist,
+            Popup::DevicePicker => &mut self.device_picker,
+            Popup::TagEditor => &mut self.tag_editor,
+            Popup::CommandLine => &mut self.command_line,
+            Popup::CommandPalette => &mut self.command_palette,
+            Popup::Help => &mut self.help,
+            Popup::Confirm => &mut self.confirm,
+            Popup::Input => &mut self.input,
+            Popup::Message => &mut self.message,
+        }
+    }
+
+    fn popup_widget(&self, popup: Popup) -> &dyn WidgetRef {
+        match popup {
+            Popup::Playlist => &self.playlist,
+            Popup::DevicePicker => &self.device_picker,
+            Popup::TagEditor => &self.tag_editor,
+            Popup::CommandLine => &self.command_line,
+            Popup::CommandPalette => &self.command_palette,
+            Popup::Help => &self.help,
+            Popup::Confirm => &self.confirm,
+            Popup::Input => &self.input,
+            Popup::Message => &self.message,
+        }
+    }
+
+    /// Escape closes any popup that doesn't handle it itself.
+    fn popup_event(&mut self, popup: Popup, key: Key) -> Result<EventState> {
+        let res = self.popup_component(popup).event(key)?;
+        if !res.is_consumed() && key == self.config.key_config.close_popup {
+            self.close_popup(popup);
+            return Ok(EventState::Consumed);
+        }
+        Ok(res)
+    }
+
+    /// Opens popup on top, moving it there if it's already open.
+    fn open_popup(&mut self, popup: Popup) {
+        self.close_popup(popup);
+        self.popups.push(popup);
+    }
+
+    /// Opens the next request waiting for `popup` once it's closed.
+    fn close_popup(&mut self, popup: Popup) {
+        let open = self.popups.len();
+        self.popups.retain(|p| *p != popup);
+        if self.popups.len() == open {
+            return;
+        }
+
+        let next = self
+            .pending_popups
+            .iter()
+            .position(|r| request_popup(r) == popup)
+            .and_then(|i| self.pending_popups.remove(i));
+        if let Some(request) = next {
+            self.open_popup_request(request);
+        }
+    }
+
+    fn open_popup_request(&mut self, request: PopupRequest) {
+        if self.popups.contains(&request_popup(&request)) {
+            self.pending_popups.push_back(request);
+            return;
+        }
+
+        match request {
+            PopupRequest::Confirm {
+                message,
+                on_confirm,
+            } => {
+                self.confirm.open(message, *on_confirm);
+                self.open_popup(Popup::Confirm);
+            }
+            PopupRequest::Input {
+                title,
+                value,
+                placeholder,
+                on_submit,
+            } => {
+                self.input.open(title, value, placeholder, on_submit);
+                self.open_popup(Popup::Input);
+            }
+        }
+    }
+
+    fn show_error(&mut self, message: String) {
+        self.message.open("Error".to_string(), message, true);
+        self.open_popup(Popup::Message);
+    }
+
+    /// Panes wide enough to show on a terminal `width` columns wide, with
+    /// the panel each shows. A focused panel whose pane collapsed takes
+    /// over the first pane.
+    fn visible_panes(&self, width: u16) -> Vec<(usize, Panel)> {
+        let mut visible = self
+            .config
+            .layout
+            .panes
+            .iter()
+            .enumerate()
+            .filter(|(_, pane)| width >= pane.min_terminal_width)
+            .map(|(i, _)| (i, self.shown[i]))
+            .collect::<Vec<_>>();
+        if visible.is_empty() {
+            visible.push((0, self.shown[0]));
+        }
+        if !visible.iter().any(|&(_, panel)| panel == self.focus) {
+            visible[0].1 = self.focus;
+        }
+        visible
+    }
+
+    /// Switches the pane having `panel` to it, panels without a visible
+    /// pane are shown in the first one.
+    fn focus_panel(&mut self, panel: Panel) -> Result<()> {
+        let visible = self.visible_panes(self.area.get().width);
+        let pane = visible
+            .iter()
+            .map(|&(pane, _)| pane)
+            .find(|&pane| self.config.layout.panes[pane].panels.contains(&panel))
+            .unwrap_or(visible[0].0);
+
+        if panel == Panel::Stats {
+            self.show_stats(self.stats.period())?;
+        }
+        self.shown[pane] = panel;
+        self.focus = panel;
+        Ok(())
+    }
+
+    /// Moves focus `step` visible panes to the right, wrapping around.
+    fn focus_next_pane(&mut self, step: isize) -> Result<()> {
+        let visible = self.visible_panes(self.area.get().width);
+        let current = visible
+            .iter()
+            .position(|&(_, panel)| panel == self.focus)
+            .unwrap_or_default();
+        let next = (current as isize + step).rem_euclid(visible.len() as isize) as usize;
+        self.focus_panel(visible[next].1)
+    }
+
+    /// Widens the focused pane by `step` percent, taking it from the pane
+    /// to its right, or to its left for the last one.
+    fn resize_pane(&mut self, step: i16) {
+        let visible = self.visible_panes(self.area.get().width);
+        let Some(current) = visible.iter().position(|&(_, panel)| panel == self.focus) else {
+            return;
+        };
+        let neighbor = match visible.get(current + 1) {
+            Some(&(pane, _)) => pane,
+            None if current > 0 => visible[current - 1].0,
+            None => return,
+        };
+        let pane = visible[current].0;
+
+        let width = self.pane_widths[pane] as i16;
+        let neighbor_width = self.pane_widths[neighbor] as i16;
+        let step = if step > 0 {
+            step.min(neighbor_width - MIN_PANE_WIDTH as i16).max(0)
+        } else {
+            step.max(MIN_PANE_WIDTH as i16 - width).min(0)
+        };
+        self.pane_widths[pane] = (self.pane_widths[pane] as i16 + step) as u16;
+        self.pane_widths[neighbor] = (self.pane_widths[neighbor] as i16 - step) as u16;
+    }
+
+    /// Top-most panel under the cursor.
+    fn panel_at(&self, column: u16, row: u16) -> Option<Panel> {
+        self.panels
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(area, _)| area.contains(Position::new(column, row)))
+            .map(|(_, focus)| *focus)
+    }
+
+    /// Keys that work regardless of focused component.
+    fn global_event(&mut self, key: Key) -> Result<EventState> {
+        let key_config = &self.config.key_config;
+
+        if key == key_config.help {
+            self.open_help();
+        } else if key == key_config.command_line {
+            self.open_command_line("");
+        } else if key == key_config.command_palette {
+            self.command_palette.open();
+            self.open_popup(Popup::CommandPalette);
+        } else if key == key_config.pick_audio_device {
+            self.device_picker.set_devices(output_devices());
+            self.open_popup(Popup::DevicePicker);
+        } else if key == key_config.focus_playlist_popup {
+            self.playlist.open(None);
+            self.open_popup(Popup::Playlist);
+        } else if key == key_config.focus_browser {
+            self.focus_panel(Panel::Browser)?;
+        } else if key == key_config.focus_stats {
+            self.focus_panel(Panel::Stats)?;
+        } else if key == key_config.focus_lyrics {
+            self.focus_panel(Panel::Lyrics)?;
+        } else if key == key_config.focus_queue {
+            self.focus_panel(Panel::Queue)?;
+        } else if key == key_config.focus_next_pane {
+            self.focus_next_pane(1)?;
+        } else if key == key_config.focus_prev_pane {
+            self.focus_next_pane(-1)?;
+        } else if key == key_config.grow_pane {
+            self.resize_pane(self.config.layout.resize_step as i16);
+        } else if key == key_config.shrink_pane {
+            self.resize_pane(-(self.config.layout.resize_step as i16));
+        } else if key == key_config.pause {
+            self.toggle_pause();
+        } else if key == key_config.skip_to_next_audio {
+            self.skip_next()?;
+        } else if key == key_config.skip_to_prev_audio {
+            self.skip_prev()?;
+        } else if key == key_config.seek_forward {
+            self.seek(self.position + SEEK_STEP);
+        } else if key == key_config.seek_backward {
+            self.seek(self.position.saturating_sub(SEEK_STEP));
+        } else if key == key_config.shuffle {
+            self.queue.set_shuffle(!self.queue.shuffle());
+        } else if key == key_config.repeat {
+            self.queue.set_repeat(self.queue.repeat().next());
+        } else if key == key_config.volume_up {
+            self.set_volume(self.volume + VOLUME_STEP);
+        } else if key == key_config.volume_down {
+            self.set_volume(self.volume - VOLUME_STEP);
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
     fn drain_commands(&mut self) -> Result<()> {
         while let Ok(cmd) = self.widget_cmd_rx.try_recv() {
             match cmd {
                 ComponentCommand::TracklistComponent(cmd) => {
                     use crate::components::tracklist::Command;
                     match cmd {
-                        Command::SetCurrentTrack { path } => {
-                            let file = std::fs::File::open(&path)?;
-                            let source = rodio::Decoder::new(file)?;
+                        Command::PlayTrack { context, index } => {
+                            if let Some(uuid) = self.queue.play_from(context, index) {
+                                self.play_track(uuid, PlayContext::Library)?;
+                            }
+                        }
+                        Command::AddToQueue { uuid } => {
+                            self.queue.push(uuid);
+                        }
+                        Command::AddToPlaylist { uuids } => {
+                            self.playlist.open(Some(uuids));
+                            self.open_popup(Popup::Playlist);
+                        }
+                        Command::RemoveFromLibrary { uuids } => self.remove_tracks(&uuids)?,
+                        Command::SetRating { uuid, rating } => self.set_rating(uuid, rating)?,
+                        Command::SetFavorite { uuid, favorite } => {
+                            self.set_favorite(uuid, favorite)?
+                        }
+                        Command::EditTags { uuids } => self.edit_tags(uuids),
+                        Command::UndoTagEdit => self.undo_tag_edit(),
+                    }
+                }
+                ComponentCommand::PlayerControlsComponent(cmd) => {
+                    use crate::components::player_controls::Command;
+                    match cmd {
+                        Command::SetRating { uuid, rating } => self.set_rating(uuid, rating)?,
+                        Command::SetFavorite { uuid, favorite } => {
+                            self.set_favorite(uuid, favorite)?
+                        }
+                        Command::Seek { pos } => self.seek(pos),
+                        Command::SetVolume { volume } => self.set_volume(volume),
+                    }
+                }
+                ComponentCommand::DevicePickerComponent(cmd) => {
+                    use crate::components::device_picker::Command;
+                    match cmd {
+                        Command::SelectDevice { name } => {
+                            _ = self.audio_tx.send(AudioCommand::SetDevice(name));
+                            self.close_popup(Popup::DevicePicker);
+                        }
+                        Command::Close => self.close_popup(Popup::DevicePicker),
+                    }
+                }
+                ComponentCommand::TagEditorComponent(cmd) => {
+                    use crate::components::tag_editor::Command;
+                    match cmd {
+                        Command::Apply { tags } => match self.write_tags(tags) {
+                            Ok(()) => self.close_popup(Popup::TagEditor),
+                            Err(e) => self.tag_editor.set_error(Some(e)),
+                        },
+                        Command::Close => self.close_popup(Popup::TagEditor),
+                    }
+                }
+                ComponentCommand::PlaylistComponent(cmd) => {
+                    use crate::components::playlist::Command;
+                    match cmd {
+                        Command::AddToPlaylist { name, tracks }
+                        | Command::CreatePlaylist { name, tracks } => {
+                            self.add_to_playlist(&name, tracks)?;
+                            self.close_popup(Popup::Playlist);
+                        }
+                        Command::PlayPlaylist { name } => {
+                            self.play_playlist(&name)?;
+                            self.close_popup(Popup::Playlist);
+                        }
+                        Command::DeletePlaylist { name } => self.delete_playlist(&name)?,
+                        Command::ExportPlaylist { name, path } => {
+                            self.export_playlist(&name, &path)?;
+                        }
+                        Command::Close => self.close_popup(Popup::Playlist),
+                    }
+                }
+                ComponentCommand::QueueComponent(cmd) => {
+                    use crate::components::queue::Command;
+                    match cmd {
+                        Command::MoveUp { index } => self.queue.swap_upcoming(index, index - 1),
+                        Command::MoveDown { index } => self.queue.swap_upcoming(index, index + 1),
+                        Command::Remove { index } => self.queue.remove_upcoming(index),
+                        Command::Clear => self.queue.clear_upcoming(),
+                        Command::ShuffleRemaining => self.queue.shuffle_upcoming(),
+                        Command::PlayFrom { index } => {
+                            if let Some((uuid, manual)) = self.queue.skip_to(index) {
+                                let context = if manual {
+                                    PlayContext::Queue
+                                } else {
+                                    PlayContext::Library
+                                };
+                                self.play_track(uuid, context)?;
+                            }
+                        }
+                        Command::Close => self.focus_panel(Panel::Tracklist)?,
+                    }
+                }
+                ComponentCommand::CommandLineComponent(cmd) => {
+                    use crate::components::command_line::Command;
+                    match cmd {
+                        Command::Run { line } => self.run_command(line),
+                        Command::Close => self.close_popup(Popup::CommandLine),
+                    }
+                }
+                ComponentCommand::CommandPaletteComponent(cmd) => {
+                    use crate::components::command_palette::Command;
+                    match cmd {
+                        Command::Run { line } => self.run_command(line),
+                        Command::Prompt { line } => {
+                            self.close_popup(Popup::CommandPalette);
+                            self.open_command_line(&line);
+                        }
+                        Command::Close => self.close_popup(Popup::CommandPalette),
+                    }
+                }
+                ComponentCommand::HelpComponent(cmd) => {
+                    use crate::components::help::Command;
+                    match cmd {
+                        Command::Close => self.close_popup(Popup::Help),
+                    }
+                }
+                ComponentCommand::ConfirmComponent(cmd) => {
+                    use crate::components::confirm::Command;
+                    match cmd {
+                        Command::Close => self.close_popup(Popup::Confirm),
+                    }
+                }
+                ComponentCommand::InputComponent(cmd) => {
+                    use crate::components::input::Command;
+                    match cmd {
+                        Command::Close => self.close_popup(Popup::Input),
+                    }
+                }
+                ComponentCommand::MessageComponent(cmd) => {
+                    use crate::components::message::Command;
+                    match cmd {
+                        Command::Close => self.close_popup(Popup::Message),
+                    }
+                }
+                ComponentCommand::OpenPopup(request) => self.open_popup_request(request),
+                ComponentCommand::BrowserComponent(cmd) => {
+                    use crate::components::browser::Command;
+                    match cmd {
+                        Command::ShowArtist { artist } => {
+                            self.tracklist.set_artist(artist);
+                            self.focus_panel(Panel::Tracklist)?;
+                        }
+                        Command::Close => self.focus_panel(Panel::Tracklist)?,
+                    }
+                }
+                ComponentCommand::LyricsComponent(cmd) => {
+                    use crate::components::lyrics::Command;
+                    match cmd {
+                        Command::Close => self.focus_panel(Panel::Tracklist)?,
+                    }
+                }
+                ComponentCommand::StatsComponent(cmd) => {
+                    use crate::components::stats::Command;
+                    match cmd {
+                        Command::SetPeriod { period } => self.show_stats(period)?,
+                        Command::Close => self.focus_panel(Panel::Tracklist)?,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-                            self.current_track = Some(CurrentTrack {
-                                path,
-                                total_duration: source.total_duration().unwrap_or(Duration::ZERO),
-                            });
+    /// Help for the top-most popup, or the focused view if there are none.
+    fn open_help(&mut self) {
+        let (name, bindings) = match self.popups.last() {
+            Some(&Popup::Help) => return,
+            Some(&popup) => (popup.name(), self.popup_component(popup).bindings()),
+            None => (self.focus.name(), self.focused_component().bindings()),
+        };
+        self.help.set_sections(vec![
+            (name, bindings),
+            ("Playing track", self.player_controls.bindings()),
+            ("Global", self.global_bindings()),
+        ]);
+        self.open_popup(Popup::Help);
+    }
 
-                            self.player_controls.name =
-                                Some(self.current_track.as_ref().unwrap().name());
+    /// Keys handled by [`App::global_event`] and the quit key.
+    fn global_bindings(&self) -> Vec<Binding> {
+        let k = &self.config.key_config;
+        vec![
+            Binding::new(k.pause, "Pause or resume"),
+            Binding::new(k.skip_to_next_audio, "Next track"),
+            Binding::new(k.skip_to_prev_audio, "Previous track"),
+            Binding::new(k.seek_forward, "Seek forward"),
+            Binding::new(k.seek_backward, "Seek backward"),
+            Binding::new(k.volume_up, "Volume up"),
+            Binding::new(k.volume_down, "Volume down"),
+            Binding::new(k.shuffle, "Toggle shuffle"),
+            Binding::new(k.repeat, "Cycle repeat mode"),
+            Binding::new(k.focus_playlist_popup, "Playlists"),
+            Binding::new(k.pick_audio_device, "Pick audio device"),
+            Binding::new(k.focus_browser, "Browse artists"),
+            Binding::new(k.focus_stats, "Listening stats"),
+            Binding::new(k.focus_lyrics, "Lyrics"),
+            Binding::new(k.focus_queue, "Queue"),
+            Binding::new(k.focus_next_pane, "Focus next pane"),
+            Binding::new(k.focus_prev_pane, "Focus previous pane"),
+            Binding::new(k.grow_pane, "Widen focused pane"),
+            Binding::new(k.shrink_pane, "Narrow focused pane"),
+            Binding::new(k.command_line, "Command line"),
+            Binding::new(k.command_palette, "Command palette"),
+            Binding::new(k.help, "Help"),
+            Binding::keys(&[k.quit, k.close_popup], "Quit, or close popup"),
+        ]
+    }
 
-                            _ = self.audio_tx.send(AudioCommand::Play(Box::new(source)));
-                        }
-                    }
+    fn open_command_line(&mut self, input: &str) {
+        self.command_line.set_completion_data(
+            self.library.iter().map(|t| t.name()).collect(),
+            self.playlists.iter().map(|p| p.name.clone()).collect(),
+        );
+        self.command_line.open(input);
+        self.open_popup(Popup::CommandLine);
+    }
+
+    /// Runs command typed on the command line or picked in the palette,
+    /// reopening the command line with the error if the command fails.
+    fn run_command(&mut self, line: String) {
+        self.close_popup(Popup::CommandLine);
+        self.close_popup(Popup::CommandPalette);
+
+        if let Err(e) = self.execute(&line) {
+            self.open_command_line(&line);
+            self.command_line.set_error(Some(e));
+        }
+    }
+
+    fn execute(&mut self, line: &str) -> Result<(), String> {
+        let action = commands::parse(line)?;
+        let failed = |e: color_eyre::Report| e.to_string();
+
+        match action {
+            Action::Play(None) => {
+                if self.current_track.is_none() {
+                    self.tracklist.play_selected().map_err(failed)?;
+                } else if self.paused {
+                    self.toggle_pause();
                 }
             }
+            Action::Play(Some(title)) => self.play_matching(&title).map_err(failed)?,
+            Action::Pause => self.toggle_pause(),
+            Action::Next => self.skip_next().map_err(failed)?,
+            Action::Prev => self.skip_prev().map_err(failed)?,
+            Action::Seek(change) => self.seek(match change {
+                Change::Set(pos) => pos,
+                Change::Add(d) => self.position + d,
+                Change::Sub(d) => self.position.saturating_sub(d),
+            }),
+            Action::Volume(change) => {
+                let fraction = |percent: u8| percent as f32 / 100.0;
+                self.set_volume(match change {
+                    Change::Set(p) => fraction(p),
+                    Change::Add(p) => self.volume + fraction(p),
+                    Change::Sub(p) => self.volume - fraction(p),
+                });
+            }
+            Action::Shuffle(shuffle) => {
+                self.queue
+                    .set_shuffle(shuffle.unwrap_or(!self.queue.shuffle()));
+            }
+            Action::Repeat(repeat) => {
+                self.queue
+                    .set_repeat(repeat.unwrap_or(self.queue.repeat().next()));
+            }
+            Action::Sort(sort) => self.tracklist.set_sort(sort),
+            Action::Filter(filter) => self.tracklist.set_filter(filter),
+            Action::Queue(title) => {
+                for uuid in self.target_tracks(title.as_deref())? {
+                    self.queue.push(uuid);
+                }
+            }
+            Action::Rate(stars, title) => {
+                for uuid in self.target_tracks(title.as_deref())? {
+                    self.set_rating(uuid, stars).map_err(failed)?;
+                }
+            }
+            Action::Favorite(title) => {
+                let uuids = self.target_tracks(title.as_deref())?;
+                let favorite = !uuids
+                    .iter()
+                    .all(|&uuid| self.track(uuid).is_some_and(|t| t.favorite));
+                for uuid in uuids {
+                    self.set_favorite(uuid, favorite).map_err(failed)?;
+                }
+            }
+            Action::Edit => {
+                let uuids = self.target_tracks(None)?;
+                self.edit_tags(uuids);
+            }
+            Action::Undo => self.undo_tag_edit(),
+            // Scripts naming the track have nobody to confirm with.
+            Action::Remove(Some(name)) if self.headless => {
+                let uuid = self.exact_match(&name)?;
+                self.remove_tracks(&[uuid]).map_err(failed)?;
+            }
+            Action::Remove(Some(name)) => {
+                let uuid = self.exact_match(&name)?;
+                self.confirm_remove(vec![uuid]);
+            }
+            Action::Remove(None) => {
+                let uuids = self.target_tracks(None)?;
+                self.confirm_remove(uuids);
+            }
+            Action::Playlist(PlaylistAction::Add, name) => {
+                let uuids = self.target_tracks(None)?;
+                if uuids.is_empty() {
+                    return Err("No tracks selected".to_string());
+                }
+                self.add_to_playlist(&name, uuids).map_err(failed)?;
+            }
+            Action::Playlist(action, name) => {
+                if !self.playlists.iter().any(|p| p.name == name) {
+                    return Err(format!("No playlist named \"{name}\""));
+                }
+                match action {
+                    PlaylistAction::Play => self.play_playlist(&name),
+                    _ => self.delete_playlist(&name),
+                }
+                .map_err(failed)?;
+            }
+            Action::Playlists => {
+                self.playlist.open(None);
+                self.open_popup(Popup::Playlist);
+            }
+            Action::Import(path) => self.import_playlist(&path).map_err(failed)?,
+            // Scripts have nobody to confirm with, like `cp` they overwrite.
+            Action::Export(name, path) if self.headless || !path.exists() => {
+                self.export_playlist(&name, &path).map_err(failed)?;
+            }
+            Action::Export(name, path) => self.confirm_export(name, path)?,
+            Action::Devices => {
+                self.device_picker.set_devices(output_devices());
+                self.open_popup(Popup::DevicePicker);
+            }
+            Action::Browser => self.focus_panel(Panel::Browser).map_err(failed)?,
+            Action::Stats => self.focus_panel(Panel::Stats).map_err(failed)?,
+            Action::Lyrics => self.focus_panel(Panel::Lyrics).map_err(failed)?,
+            Action::Quit => self.quit = true,
+        }
+
+        Ok(())
+    }
+
+    /// Track whose title best matches `title`.
+    fn best_match(&self, title: &str) -> Option<Uuid> {
+        self.library
+            .iter()
+            .filter_map(|t| Some((fuzzy_score(title, &t.name())?, t.uuid)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, uuid)| uuid)
+    }
+
+    /// Track `name` is the exact title, path or UUID of. Tracks sharing the
+    /// title are refused rather than guessed between, for commands that
+    /// can't be undone.
+    fn exact_match(&self, name: &str) -> Result<Uuid, String> {
+        if let Ok(uuid) = Uuid::parse_str(name)
+            && self.track(uuid).is_some()
+        {
+            return Ok(uuid);
+        }
+
+        let matches = self
+            .library
+            .iter()
+            .filter(|t| t.path == Path::new(name) || t.name() == name)
+            .map(|t| t.uuid)
+            .collect::<Vec<_>>();
+        match matches[..] {
+            [uuid] => Ok(uuid),
+            [] => Err(format!("No track has title, path or UUID \"{name}\"")),
+            _ => Err(format!(
+                "{} tracks are titled \"{name}\", give the path or UUID",
+                matches.len()
+            )),
+        }
+    }
+
+    /// Tracks a command acts on, the best match for `title` or else the
+    /// tracklist selection. Headless mode has no tracklist to select in,
+    /// commands there have to name their track.
+    fn target_tracks(&self, title: Option<&str>) -> Result<Vec<Uuid>, String> {
+        match title {
+            Some(title) => self
+                .best_match(title)
+                .map(|uuid| vec![uuid])
+                .ok_or_else(|| format!("No track matches \"{title}\"")),
+            None if self.headless => {
+                Err("Without the tracklist there's no selection, name the track".to_string())
+            }
+            None => Ok(self.tracklist.selected_uuids()),
+        }
+    }
+
+    /// Asks first like the tracklist does, removed tracks only come back by
+    /// editing the database.
+    fn confirm_remove(&mut self, uuids: Vec<Uuid>) {
+        let message = match uuids.len() {
+            0 => return,
+            1 => "Remove track from library?".to_string(),
+            n => format!("Remove {n} tracks from library?"),
+        };
+        self.open_popup_request(PopupRequest::Confirm {
+            message,
+            on_confirm: Box::new(ComponentCommand::TracklistComponent(
+                TracklistCommand::RemoveFromLibrary { uuids },
+            )),
+        });
+    }
+
+    /// Asks before replacing a file that's already at `path`.
+    fn confirm_export(&mut self, name: String, path: PathBuf) -> Result<(), String> {
+        if !self.playlists.iter().any(|p| p.name == name) {
+            return Err(format!("No playlist named \"{name}\""));
+        }
+
+        self.open_popup_request(PopupRequest::Confirm {
+            message: format!("Overwrite {}?", path.display()),
+            on_confirm: Box::new(ComponentCommand::PlaylistComponent(
+                PlaylistCommand::ExportPlaylist { name, path },
+            )),
+        });
+        Ok(())
+    }
+
+    /// Plays track whose title best matches `title`, continuing through the
+    /// tracklist if it's shown there.
+    fn play_matching(&mut self, title: &str) -> Result<()> {
+        let Some(uuid) = self.best_match(title) else {
+            return Err(eyre!("No track matches \"{title}\""));
+        };
+
+        let shown = self
+            .tracklist
+            .tracks()
+            .iter()
+            .map(|t| t.uuid)
+            .collect::<Vec<_>>();
+        let (context, index) = match shown.iter().position(|&u| u == uuid) {
+            Some(index) => (shown, index),
+            None => (vec![uuid], 0),
+        };
+
+        if let Some(uuid) = self.queue.play_from(context, index) {
+            self.play_track(uuid, PlayContext::Library)?;
+        }
+
+        Ok(())
+    }
+
+    fn skip_next(&mut self) -> Result<()> {
+        if let Some((uuid, context)) = self.next_track() {
+            self.play_track(uuid, context)?;
+        }
+        Ok(())
+    }
+
+    fn skip_prev(&mut self) -> Result<()> {
+        if let Some(uuid) = self.queue.prev() {
+            self.play_track(uuid, PlayContext::Library)?;
+        }
+        Ok(())
+    }
+
+    fn edit_tags(&mut self, uuids: Vec<Uuid>) {
+        let tracks = uuids
+            .into_iter()
+            .filter_map(|uuid| self.track(uuid).cloned())
+            .collect::<Vec<_>>();
+        if !tracks.is_empty() {
+            self.tag_editor.set_tracks(tracks);
+            self.open_popup(Popup::TagEditor);
+        }
+    }
+
+    fn undo_tag_edit(&mut self) {
+        let tags = std::mem::take(&mut self.last_tag_edit);
+        if let Err(e) = self.write_tags(tags) {
+            self.show_error(e);
+        }
+        // Undoing the undo isn't supported.
+        self.last_tag_edit.clear();
+    }
+
+    fn remove_tracks(&mut self, uuids: &[Uuid]) -> Result<()> {
+        db::remove_tracks(&self.sqlite, uuids)?;
+        self.library.retain(|t| !uuids.contains(&t.uuid));
+        self.tracklist.remove_tracks(uuids);
+        self.update_browser();
+        Ok(())
+    }
+
+    fn play_playlist(&mut self, name: &str) -> Result<()> {
+        let tracks = self.playlist_tracks(name);
+        if let Some(uuid) = self.queue.play_from(tracks, 0) {
+            self.play_track(uuid, PlayContext::Library)?;
+        }
+        Ok(())
+    }
+
+    fn delete_playlist(&mut self, name: &str) -> Result<()> {
+        db::delete_playlist(&self.sqlite, name)?;
+        self.playlists.retain(|p| p.name != name);
+        self.playlist.set_playlists(&self.playlists);
+        Ok(())
+    }
+
+    /// Appends tracks to the playlist, creating it if there's none with that
+    /// name.
+    fn add_to_playlist(&mut self, name: &str, tracks: Vec<Uuid>) -> Result<()> {
+        let index = match self.playlists.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                self.playlists.push(Playlist {
+                    name: name.to_string(),
+                    tracks: vec![],
+                });
+                self.playlists.sort_by(|a, b| a.name.cmp(&b.name));
+                self.playlists.iter().position(|p| p.name == name).unwrap()
+            }
+        };
+
+        let playlist = &mut self.playlists[index];
+        playlist.tracks.extend(tracks);
+        db::save_playlist(&mut self.sqlite, playlist)?;
+        self.playlist.set_playlists(&self.playlists);
+
+        Ok(())
+    }
+
+    /// Saves playlist from the file under the name it has there, replacing a
+    /// playlist with that name. Lists entries that weren't found.
+    fn import_playlist(&mut self, path: &Path) -> Result<()> {
+        let roots = [self.config.audio_dir.clone()];
+        let import = playlist_file::import(path, &roots, &self.library)?;
+
+        self.playlists.retain(|p| p.name != import.name);
+        let count = import.tracks.len();
+        self.add_to_playlist(&import.name, import.tracks)?;
+
+        let mut report = format!("Imported {count} tracks into \"{}\"", import.name);
+        if import.moved > 0 {
+            report += &format!("\n{} were found elsewhere by their tags", import.moved);
+        }
+        if !import.unresolved.is_empty() {
+            report += &format!("\n\n{} entries weren't found:", import.unresolved.len());
+            for location in import.unresolved.iter() {
+                report += &format!("\n  {location}");
+            }
+        }
+        self.message.open("Import".to_string(), report, false);
+        self.open_popup(Popup::Message);
+
+        Ok(())
+    }
+
+    /// Writes playlist `name` to `path` in the format its extension names.
+    fn export_playlist(&mut self, name: &str, path: &Path) -> Result<()> {
+        if !self.playlists.iter().any(|p| p.name == name) {
+            return Err(eyre!("No playlist named \"{name}\""));
         }
 
+        let tracks = self.playlist_tracks(name);
+        let tracks = tracks
+            .iter()
+            .filter_map(|&uuid| self.track(uuid))
+            .collect::<Vec<_>>();
+        playlist_file::export(path, PlaylistFormat::from_path(path)?, name, &tracks)
+    }
+
+    /// Tracks of the playlist that are still in the library.
+    fn playlist_tracks(&self, name: &str) -> Vec<Uuid> {
+        self.playlists
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| {
+                p.tracks
+                    .iter()
+                    .copied()
+                    .filter(|&uuid| self.track(uuid).is_some())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Copies player state shown by the controls.
+    fn update_controls(&mut self) {
+        let controls = &mut self.player_controls;
+        controls.position = self.position;
+        controls.duration = self
+            .current_track
+            .as_ref()
+            .map(|t| t.total_duration)
+            .unwrap_or_default();
+        controls.paused = self.paused;
+        controls.volume = self.volume;
+        controls.shuffle = self.queue.shuffle();
+        controls.repeat = self.queue.repeat();
+    }
+
+    /// Lists upcoming tracks in the queue view, skipped while no pane shows
+    /// it.
+    fn update_queue_view(&mut self) {
+        if !self.shown.contains(&Panel::Queue) && self.focus != Panel::Queue {
+            return;
+        }
+
+        let names = self
+            .library
+            .iter()
+            .map(|t| (t.uuid, t))
+            .collect::<HashMap<_, _>>();
+        let entries = self
+            .queue
+            .upcoming()
+            .map(|(uuid, manual)| {
+                // Rows have to line up with the queue, tracks removed from
+                // the library still get one.
+                let name = names
+                    .get(&uuid)
+                    .map_or_else(|| "Unknown track".to_string(), |t| t.name());
+                QueueEntry { name, manual }
+            })
+            .collect();
+        self.queue_view.set_entries(entries);
+    }
+
+    /// Marks the playing track in every list.
+    fn set_playing(&mut self, uuid: Option<Uuid>) {
+        self.tracklist.set_playing(uuid);
+        self.stats.set_playing(uuid);
+    }
+
+    fn show_stats(&mut self, period: Period) -> Result<()> {
+        let history = db::history(&self.sqlite, period.since())?;
+        self.stats
+            .set_stats(period, ListeningStats::compute(&history, &self.library));
         Ok(())
     }
+
+    fn track(&self, uuid: Uuid) -> Option<&Track> {
+        self.library.iter().find(|t| t.uuid == uuid)
+    }
+
+    fn next_track(&mut self) -> Option<(Uuid, PlayContext)> {
+        let context = if self.queue.manual.is_empty() {
+            PlayContext::Library
+        } else {
+            PlayContext::Queue
+        };
+
+        self.queue.next().map(|uuid| (uuid, context))
+    }
+
+    /// Plays track from the library, resuming long tracks where they were
+    /// left off. Track that was playing is recorded as skipped.
+    fn play_track(&mut self, uuid: Uuid, context: PlayContext) -> Result<()> {
+        self.save_resume_position()?;
+        self.finish_play(true)?;
+
+        let Some(track) = self.track(uuid).cloned() else {
+            return Ok(());
+        };
+
+        let pos = if self.is_resumable(&track) {
+            db::resume_position(&self.sqlite, uuid)?.unwrap_or_default()
+        } else {
+            Duration::ZERO
+        };
+
+        self.start(&track, pos, false, context)
+    }
+
+    fn start(
+        &mut self,
+        track: &Track,
+        pos: Duration,
+        paused: bool,
+        context: PlayContext,
+    ) -> Result<()> {
+        let file = std::fs::File::open(&track.path)?;
+        let source = rodio::Decoder::new(file)?;
+
+        // Decoder rarely knows duration of VBR files, metadata duration is
+        // filled in by a scan when headers don't have it either.
+        let total_duration = if track.duration.is_zero() {
+            source.total_duration().unwrap_or(Duration::ZERO)
+        } else {
+            track.duration
+        };
+        let current_track =
+            CurrentTrack::new(track.uuid, track.path.clone(), total_duration, context);
+        self.player_controls.name = Some(current_track.name());
+        self.player_controls.set_track(Some(track));
+        self.set_playing(Some(track.uuid));
+        self.cover_art.set_track(Some(&track.path));
+        self.lyrics.set_lyrics(io::read_lyrics(&track.path));
+        self.lyrics.set_position(pos);
+
+        _ = self.audio_tx.send(AudioCommand::Play {
+            path: current_track.path.clone(),
+            source: Box::new(source),
+            pos,
+            paused,
+        });
+
+        self.current_track = Some(current_track);
+        self.position = pos;
+        self.paused = paused;
+
+        Ok(())
+    }
+
+    /// Writes current track into play history and stops tracking it. A
+    /// track nothing was heard of, e.g. one restored paused and switched
+    /// away from, is neither a play nor a skip.
+    fn finish_play(&mut self, skipped: bool) -> Result<()> {
+        let Some(current_track) = self.current_track.take() else {
+            return Ok(());
+        };
+        if current_track.listened.is_zero() {
+            return Ok(());
+        }
+
+        let play = Play {
+            track: current_track.uuid,
+            started_at: current_track.started_at,
+            listened: current_track.listened,
+            skipped,
+            context: current_track.context,
+        };
+        db::record_play(&self.sqlite, &play)?;
+
+        if let Some(track) = self.library.iter_mut().find(|t| t.uuid == play.track) {
+            if skipped {
+                track.stats.skip_count += 1;
+            } else {
+                track.stats.play_count += 1;
+            }
+            track.stats.last_played = Some(play.started_at);
+            self.tracklist.update_track(track);
+        }
+
+        Ok(())
+    }
+
+    /// Writes tags into files and the library, remembering previous tags for
+    /// undo. Stops at the first file that fails to be written.
+    fn write_tags(&mut self, tags: Vec<(Uuid, TrackTags)>) -> Result<(), String> {
+        self.last_tag_edit.clear();
+
+        let mut res = Ok(());
+        for (uuid, tags) in tags {
+            let Some(track) = self.library.iter_mut().find(|t| t.uuid == uuid) else {
+                continue;
+            };
+            if track.tags == tags {
+                continue;
+            }
+
+            if let Err(e) = io::write_tags(&track.path, &tags) {
+                res = Err(format!("Failed to write {}: {e}", track.path.display()));
+                break;
+            }
+
+            let old = std::mem::replace(&mut track.tags, tags);
+            self.last_tag_edit.push((uuid, old));
+            self.tracklist.update_track(track);
+        }
+
+        // Files written before a failure may have changed artists too.
+        self.update_browser();
+        res
+    }
+
+    /// Relists artists after tracks changed, and the tracklist with them in
+    /// case the artist it's narrowed to is gone or tracks moved in or out.
+    fn update_browser(&mut self) {
+        self.browser.set_library(&self.library);
+        self.tracklist
+            .set_artist(self.browser.shown().map(str::to_string));
+    }
+
+    fn set_rating(&mut self, uuid: Uuid, rating: u8) -> Result<()> {
+        let Some(track) = self.library.iter_mut().find(|t| t.uuid == uuid) else {
+            return Ok(());
+        };
+        track.rating = rating;
+
+        if self.config.write_ratings_to_files
+            && let Err(e) = io::write_rating(&track.path, rating)
+        {
+            self.player_controls.error = Some(format!("Failed to write rating: {e}"));
+        }
+
+        self.save_rating(uuid)
+    }
+
+    fn set_favorite(&mut self, uuid: Uuid, favorite: bool) -> Result<()> {
+        if let Some(track) = self.library.iter_mut().find(|t| t.uuid == uuid) {
+            track.favorite = favorite;
+        }
+
+        self.save_rating(uuid)
+    }
+
+    /// Stores rating of the track and shows it everywhere.
+    fn save_rating(&mut self, uuid: Uuid) -> Result<()> {
+        let Some(track) = self.track(uuid).cloned() else {
+            return Ok(());
+        };
+
+        db::save_rating(&self.sqlite, uuid, track.rating, track.favorite)?;
+        self.tracklist.update_track(&track);
+        if self.current_track.as_ref().is_some_and(|t| t.uuid == uuid) {
+            self.player_controls.set_track(Some(&track));
+        }
+
+        Ok(())
+    }
+
+    fn is_resumable(&self, track: &Track) -> bool {
+        track.duration >= self.config.resume_min_duration
+    }
+
+    fn save_resume_position(&self) -> Result<()> {
+        let Some(current_track) = self.current_track.as_ref() else {
+            return Ok(());
+        };
+
+        if let Some(track) = self.track(current_track.uuid)
+            && self.is_resumable(track)
+        {
+            db::save_resume_position(&self.sqlite, track.uuid, self.position)?;
+        }
+
+        Ok(())
+    }
+
+    fn toggle_pause(&mut self) {
+        if self.current_track.is_none() {
+            return;
+        }
+
+        self.paused = !self.paused;
+        _ = self.audio_tx.send(if self.paused {
+            AudioCommand::Pause
+        } else {
+            AudioCommand::Resume
+        });
+    }
+
+    fn seek(&mut self, pos: Duration) {
+        let Some(current_track) = self.current_track.as_ref() else {
+            return;
+        };
+
+        let pos = if current_track.total_duration.is_zero() {
+            pos
+        } else {
+            pos.min(current_track.total_duration)
+        };
+
+        self.position = pos;
+        _ = self.audio_tx.send(AudioCommand::Seek(pos));
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        _ = self.audio_tx.send(AudioCommand::SetVolume(self.volume));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::audio_thread::AudioThread;
+    use crate::audio_thread::tests::{TestFile, config};
+    use crate::event::Event;
+    use crate::models::TrackStats;
+
+    use super::*;
+
+    /// App with an empty library, playing into a null backend. Library
+    /// directory is deleted on drop.
+    struct TestApp {
+        app: App,
+        events: Receiver<Event>,
+        dir: PathBuf,
+    }
+
+    impl TestApp {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("mood-{}", Uuid::new_v4()));
+            std::fs::create_dir(&dir).unwrap();
+
+            let (command_tx, command_rx) = crossbeam_channel::unbounded();
+            let (event_tx, event_rx) = crossbeam_channel::unbounded();
+            let config = config(dir.clone());
+            AudioThread::new(command_rx, event_tx, &config)
+                .run()
+                .unwrap();
+            let sqlite = Connection::open_in_memory().unwrap();
+            let app = App::without_session(command_tx, config, sqlite).unwrap();
+
+            TestApp {
+                app,
+                events: event_rx,
+                dir,
+            }
+        }
+
+        fn add_track(&mut self, file: &TestFile, title: &str) -> Uuid {
+            let uuid = Uuid::new_v4();
+            self.app.library.push(Track {
+                uuid,
+                duration: Duration::ZERO,
+                path: file.0.clone(),
+                tags: TrackTags {
+                    title: Some(title.to_string()),
+                    ..TrackTags::default()
+                },
+                stats: TrackStats::default(),
+                rating: 0,
+                favorite: false,
+            });
+            uuid
+        }
+
+        /// Passes audio messages to the app until `done`.
+        fn run_until(&mut self, done: impl Fn(&App) -> bool) {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !done(&self.app) {
+                match self.events.recv_deadline(deadline) {
+                    Ok(Event::Audio(msg)) => _ = self.app.audio(msg).unwrap(),
+                    Ok(_) => {}
+                    Err(_) => panic!("Timed out waiting for the app"),
+                }
+            }
+        }
+    }
+
+    impl Drop for TestApp {
+        fn drop(&mut self) {
+            _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn queue_moves_to_next_track() {
+        let mut test = TestApp::new();
+        let files = [
+            TestFile::new(Duration::from_secs(1)),
+            TestFile::new(Duration::from_secs(1)),
+        ];
+        let uuids = files
+            .iter()
+            .map(|f| test.add_track(f, "Song"))
+            .collect::<Vec<_>>();
+
+        test.app.play_tracks(uuids.clone()).unwrap();
+        assert_eq!(test.app.status().track, Some(uuids[0]));
+
+        test.run_until(|app| app.status().track != Some(uuids[0]));
+        assert_eq!(test.app.status().track, Some(uuids[1]));
+
+        test.run_until(|app| app.status().track.is_none());
+    }
+
+    #[test]
+    fn remove_needs_exact_name() {
+        let mut test = TestApp::new();
+        let files = [
+            TestFile::new(Duration::ZERO),
+            TestFile::new(Duration::ZERO),
+            TestFile::new(Duration::ZERO),
+        ];
+        let first = test.add_track(&files[0], "Song");
+        let second = test.add_track(&files[1], "Song");
+        let other = test.add_track(&files[2], "Other");
+        test.app.set_headless(true);
+
+        assert!(test.app.command("remove Othe").is_err());
+        assert!(test.app.command("remove Song").is_err());
+        assert_eq!(test.app.library().len(), 3);
+
+        test.app.command("remove Other").unwrap();
+        test.app.command(&format!("remove {first}")).unwrap();
+        let path = files[1].0.to_str().unwrap();
+        test.app.command(&format!("remove {path}")).unwrap();
+        assert!(test.app.library().is_empty());
+
+        let removed = db::removed_tracks(&test.app.sqlite).unwrap();
+        assert!([first, second, other].iter().all(|u| removed.contains(u)));
+    }
+
+    #[test]
+    fn export_asks_before_overwriting() {
+        let mut test = TestApp::new();
+        let file = TestFile::new(Duration::ZERO);
+        let uuid = test.add_track(&file, "Song");
+        test.app.add_to_playlist("Mix", vec![uuid]).unwrap();
+
+        let new = test.dir.join("new.m3u");
+        test.app
+            .command(&format!("export Mix {}", new.display()))
+            .unwrap();
+        assert!(std::fs::read_to_string(&new).unwrap().contains("Song"));
+        assert!(test.app.popups.is_empty());
+
+        let old = test.dir.join("old.m3u");
+        std::fs::write(&old, "old").unwrap();
+        let export = format!("export Mix {}", old.display());
+        test.app.command(&export).unwrap();
+        assert_eq!(test.app.popups, [Popup::Confirm]);
+        assert_eq!(std::fs::read_to_string(&old).unwrap(), "old");
+
+        test.app.event(Key::Esc).unwrap();
+        assert!(test.app.popups.is_empty());
+        assert_eq!(std::fs::read_to_string(&old).unwrap(), "old");
+
+        test.app.command(&export).unwrap();
+        test.app.event(Key::Enter).unwrap();
+        assert!(test.app.popups.is_empty());
+        assert!(std::fs::read_to_string(&old).unwrap().contains("Song"));
+
+        assert!(test.app.command("export Nope /tmp/nope.m3u").is_err());
+    }
+
+    #[test]
+    fn records_time_played() {
+        let mut test = TestApp::new();
+        let file = TestFile::new(Duration::from_secs(1));
+        let uuid = test.add_track(&file, "Song");
+
+        test.app.play_tracks(vec![uuid]).unwrap();
+        test.run_until(|app| app.status().track.is_none());
+
+        let history = db::history(&test.app.sqlite, None).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(!history[0].skipped);
+        assert!(history[0].listened > Duration::from_millis(500));
+        assert!(history[0].listened <= Duration::from_secs(1));
+        assert_eq!(test.app.track(uuid).unwrap().stats.play_count, 1);
+    }
+
+    #[test]
+    fn skipped_time_isnt_listened() {
+        let mut test = TestApp::new();
+        let file = TestFile::new(Duration::from_secs(4));
+        let uuid = test.add_track(&file, "Song");
+
+        test.app.play_tracks(vec![uuid]).unwrap();
+        test.app.command("seek 3").unwrap();
+        test.run_until(|app| app.status().track.is_none());
+
+        let history = db::history(&test.app.sqlite, None).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].listened <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn records_play_interrupted_by_quit() {
+        let mut test = TestApp::new();
+        let file = TestFile::new(Duration::from_secs(4));
+        let uuid = test.add_track(&file, "Song");
+
+        test.app.play_tracks(vec![uuid]).unwrap();
+        test.run_until(|app| app.status().position >= Duration::from_secs(1));
+        test.app.save_session().unwrap();
+        test.app.record_unfinished_play().unwrap();
+
+        let history = db::history(&test.app.sqlite, None).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(!history[0].skipped);
+        assert!(history[0].listened >= Duration::from_secs(1));
+        // Session still picks up where it was left off.
+        let session = db::load_session(&test.app.sqlite).unwrap().unwrap();
+        assert_eq!(session.track, Some(uuid));
+    }
 }
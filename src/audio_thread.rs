@@ -1,67 +1,454 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
 use color_eyre::Result;
-use crossbeam_channel::{Receiver, Sender};
-use std::time::Duration;
+use color_eyre::eyre::eyre;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use rodio::Sink;
 
-use crate::{
-    event::{AudioMessage, Command, Event},
-    source::NotifySource,
-};
+use crate::backend::{AudioBackend, BackendKind, DeviceBackend, NullBackend, WavBackend};
+use crate::config::Config;
+use crate::event::{AudioMessage, Command, Event};
+use crate::source::{NotifySource, Position};
 
 pub struct AudioThread {
     command_rx: Receiver<Command>,
     event_tx: Sender<Event>,
+    backend: BackendKind,
+    device: Option<String>,
+    position_interval: Duration,
+}
+
+/// Opened backend together with the sink playing into it.
+///
+/// Backend must outlive the sink, otherwise playback stops.
+struct Output {
+    sink: Sink,
+    backend: Box<dyn AudioBackend>,
+}
+
+/// Track appended to the sink.
+struct Playing {
+    path: PathBuf,
+    position: Position,
+    /// Played on outputs used before the current one.
+    played_before: Duration,
+    /// Last position sent to the app.
+    reported: Duration,
+    /// When position last moved, or playback last resumed.
+    moved_at: Instant,
+    stalled: bool,
+}
+
+impl Playing {
+    fn new(path: PathBuf, position: Position) -> Self {
+        Playing {
+            path,
+            reported: position.get(),
+            position,
+            played_before: Duration::ZERO,
+            moved_at: Instant::now(),
+            stalled: false,
+        }
+    }
+
+    fn progress(&self) -> AudioMessage {
+        AudioMessage::Position {
+            pos: self.reported,
+            played: self.played_before + self.position.played(),
+        }
+    }
 }
 
 impl AudioThread {
-    pub fn new(command_rx: Receiver<Command>, event_tx: Sender<Event>) -> AudioThread {
+    pub fn new(command_rx: Receiver<Command>, event_tx: Sender<Event>, config: &Config) -> Self {
         AudioThread {
             command_rx,
             event_tx,
+            backend: config.audio_backend.clone(),
+            device: config.audio_device.clone(),
+            position_interval: config.position_update_interval,
         }
     }
 
-    pub fn run(self) -> Result<()> {
+    pub fn run(mut self) -> Result<()> {
         _ = std::thread::spawn(move || -> Result<()> {
-            let stream_handle = rodio::OutputStreamBuilder::open_default_stream()
-                .expect("open default audio stream");
-            let sink = rodio::Sink::connect_new(&stream_handle.mixer());
-            sink.set_volume(0.05);
+            let mut output = self.open_output(&self.backend, 0.05);
+            let mut playing: Option<Playing> = None;
 
             loop {
-                // Accept command
-                let cmd = self.command_rx.recv()?;
+                // Accept command, report progress while there is none
+                let cmd = match self.command_rx.recv_timeout(self.position_interval) {
+                    Ok(cmd) => cmd,
+                    Err(RecvTimeoutError::Timeout) => {
+                        if let (Some(output), Some(playing)) = (output.as_ref(), playing.as_mut()) {
+                            self.report_progress(output, playing);
+                        }
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                };
 
                 match cmd {
-                    Command::Play(source) => {
+                    Command::Play {
+                        path,
+                        source,
+                        pos,
+                        paused,
+                    } => {
+                        let Some(output) = output.as_ref() else {
+                            self.send_error("No audio output device available");
+                            continue;
+                        };
+
                         let notify_source = NotifySource::new(*source, self.event_tx.clone());
-                        sink.clear();
-                        sink.append(notify_source);
-                        sink.play();
+                        let position = notify_source.position();
+                        output.sink.clear();
+                        output.sink.append(notify_source);
+                        if !pos.is_zero()
+                            && let Err(e) = output.sink.try_seek(pos)
+                        {
+                            self.send_error(e.to_string());
+                        }
+                        if !paused {
+                            output.sink.play();
+                        }
+
+                        let started = Playing::new(path, position);
+                        self.send(AudioMessage::Started { paused });
+                        self.send(started.progress());
+                        playing = Some(started);
+                    }
+                    Command::Pause => {
+                        if let Some(output) = output.as_ref() {
+                            output.sink.pause();
+                            self.send(AudioMessage::Paused);
+                        }
+                    }
+                    Command::Resume => {
+                        if let Some(output) = output.as_ref() {
+                            output.sink.play();
+                            self.send(AudioMessage::Resumed);
+                        }
+                        if let Some(playing) = playing.as_mut() {
+                            playing.moved_at = Instant::now();
+                        }
                     }
-                    Command::SendState => {
-                        let state = SinkState {
-                            pos: sink.get_pos(),
-                            volume: sink.volume(),
+                    Command::Seek(pos) => {
+                        if let (Some(output), Some(playing)) = (output.as_ref(), playing.as_mut()) {
+                            match output.sink.try_seek(pos) {
+                                Ok(()) => {
+                                    playing.reported = playing.position.get();
+                                    self.send(playing.progress());
+                                }
+                                Err(e) => self.send_error(e.to_string()),
+                            }
+                        }
+                    }
+                    Command::SetVolume(volume) => {
+                        if let Some(output) = output.as_ref() {
+                            output.sink.set_volume(volume);
+                            self.send(AudioMessage::VolumeChanged(volume));
+                        }
+                    }
+                    Command::SetDevice(name) => {
+                        let pos = playing
+                            .as_ref()
+                            .map(|p| p.position.get())
+                            .unwrap_or_default();
+                        let (volume, paused) = match output.as_ref() {
+                            Some(o) => (o.sink.volume(), o.sink.is_paused()),
+                            None => (0.05, false),
                         };
 
-                        _ = self.event_tx.send(Event::Audio(AudioMessage::State(state)));
-                    }
-                    Command::Noop => {
-                        continue;
+                        // Old stream has to be dropped before opening a new one, some
+                        // hosts refuse to open the same device twice.
+                        drop(output.take());
+                        self.backend = BackendKind::Device;
+                        self.device = Some(name);
+                        output = self.open_output(&self.backend, volume);
+
+                        if let (Some(output), Some(playing)) = (output.as_ref(), playing.as_mut()) {
+                            match self.restore(output, &playing.path, pos, paused) {
+                                Ok(position) => {
+                                    playing.played_before += playing.position.played();
+                                    playing.position = position;
+                                }
+                                Err(e) => self.send_error(e.to_string()),
+                            }
+                        }
                     }
                 }
-
-                // Emmit event
-                self.event_tx.send(Event::Audio(AudioMessage::Noop))?;
             }
         });
 
         Ok(())
     }
+
+    /// Sends position if it moved since the last report. Position standing
+    /// still for longer than the output pulls samples in while the sink is
+    /// playing means the output ran dry. Movement is only noticed here, so
+    /// that's a buffer period plus one check interval. Outputs take a while
+    /// to pull the first samples of a track, that's not an underrun either.
+    fn report_progress(&self, output: &Output, playing: &mut Playing) {
+        if output.sink.is_paused() || output.sink.empty() {
+            playing.moved_at = Instant::now();
+            return;
+        }
+
+        let pos = playing.position.get();
+        if pos != playing.reported {
+            playing.stalled = false;
+            playing.moved_at = Instant::now();
+            playing.reported = pos;
+            self.send(playing.progress());
+            return;
+        }
+
+        if !playing.stalled
+            && !playing.position.played().is_zero()
+            && playing.moved_at.elapsed() > output.backend.buffer_period() + self.position_interval
+        {
+            playing.stalled = true;
+            self.send(AudioMessage::Underrun);
+        }
+    }
+
+    /// Opens configured backend, for device backend falls back to the
+    /// default device. On failure reports error to the app and returns `None`
+    /// so the UI keeps running without audio.
+    fn open_output(&self, backend: &BackendKind, volume: f32) -> Option<Output> {
+        let backend: Result<Box<dyn AudioBackend>> = match backend {
+            BackendKind::Device => self.open_device(self.device.as_deref()),
+            BackendKind::Null { speed } => NullBackend::new(*speed).map(|b| Box::new(b) as _),
+            BackendKind::Wav { path, speed } => {
+                WavBackend::create(path, *speed).map(|b| Box::new(b) as _)
+            }
+        };
+
+        match backend {
+            Ok(backend) => {
+                let sink = Sink::connect_new(backend.mixer());
+                sink.set_volume(volume);
+
+                self.send(AudioMessage::DeviceChanged(
+                    backend.device_name().map(String::from),
+                ));
+
+                Some(Output { sink, backend })
+            }
+            Err(e) => {
+                self.send_error(format!("Failed to open audio output: {e}"));
+                None
+            }
+        }
+    }
+
+    fn open_device(&self, name: Option<&str>) -> Result<Box<dyn AudioBackend>> {
+        let device = match DeviceBackend::open(name) {
+            Err(e) if name.is_some() => {
+                self.send_error(format!("{e}, falling back to default device"));
+                DeviceBackend::open(None)?
+            }
+            device => device?,
+        };
+
+        Ok(Box::new(device))
+    }
+
+    /// Reopens track on a new output keeping position and pause state.
+    fn restore(
+        &self,
+        output: &Output,
+        path: &Path,
+        pos: Duration,
+        paused: bool,
+    ) -> Result<Position> {
+        let file = std::fs::File::open(path)?;
+        let source = NotifySource::new(rodio::Decoder::new(file)?, self.event_tx.clone());
+        let position = source.position();
+
+        output.sink.append(source);
+        output.sink.try_seek(pos).map_err(|e| eyre!("{e}"))?;
+        if paused {
+            output.sink.pause();
+        }
+
+        Ok(position)
+    }
+
+    fn send(&self, msg: AudioMessage) {
+        _ = self.event_tx.send(Event::Audio(msg));
+    }
+
+    fn send_error(&self, msg: impl Into<String>) {
+        self.send(AudioMessage::Error(msg.into()));
+    }
 }
 
-#[derive(Clone)]
-pub struct SinkState {
-    pub pos: Duration,
-    pub volume: f32,
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::fs::File;
+    use std::time::Instant;
+
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use rodio::Decoder;
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// How much faster than real-time tests play.
+    const SPEED: f32 = 10.0;
+
+    /// WAV file of silence in the temp dir, deleted on drop.
+    pub(crate) struct TestFile(pub(crate) PathBuf);
+
+    impl TestFile {
+        pub(crate) fn new(length: Duration) -> Self {
+            let path = std::env::temp_dir().join(format!("mood-{}.wav", Uuid::new_v4()));
+            let spec = WavSpec {
+                channels: 1,
+                sample_rate: 8_000,
+                bits_per_sample: 16,
+                sample_format: SampleFormat::Int,
+            };
+            let mut writer = WavWriter::create(&path, spec).unwrap();
+            for _ in 0..(length.as_secs_f64() * spec.sample_rate as f64) as u64 {
+                writer.write_sample(0i16).unwrap();
+            }
+            writer.finalize().unwrap();
+
+            TestFile(path)
+        }
+
+        fn play(&self, pos: Duration) -> Command {
+            let source = Decoder::new(File::open(&self.0).unwrap()).unwrap();
+            Command::Play {
+                path: self.0.clone(),
+                source: Box::new(source),
+                pos,
+                paused: false,
+            }
+        }
+    }
+
+    impl Drop for TestFile {
+        fn drop(&mut self) {
+            _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Config playing into a null backend at [`SPEED`].
+    pub(crate) fn config(audio_dir: PathBuf) -> Config {
+        let mut config = Config::new(audio_dir);
+        config.audio_backend = BackendKind::Null { speed: SPEED };
+        config.position_update_interval = Duration::from_millis(5);
+        config
+    }
+
+    fn spawn(config: &Config) -> (Sender<Command>, Receiver<Event>) {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let (event_tx, event_rx) = crossbeam_channel::unbounded();
+        AudioThread::new(command_rx, event_tx, config)
+            .run()
+            .unwrap();
+        (command_tx, event_rx)
+    }
+
+    /// Skips messages until one `accept` returns `Some` for.
+    fn wait_for<T>(events: &Receiver<Event>, accept: impl Fn(AudioMessage) -> Option<T>) -> T {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match events.recv_deadline(deadline) {
+                Ok(Event::Audio(msg)) => {
+                    if let Some(found) = accept(msg) {
+                        return found;
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => panic!("Timed out waiting for audio message"),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_playback() {
+        let (commands, events) = spawn(&config(PathBuf::new()));
+        let file = TestFile::new(Duration::from_secs(2));
+
+        commands.send(file.play(Duration::ZERO)).unwrap();
+        let paused = wait_for(&events, |m| match m {
+            AudioMessage::Started { paused } => Some(paused),
+            _ => None,
+        });
+        assert!(!paused);
+        wait_for(&events, |m| match m {
+            AudioMessage::Position { pos, .. } if !pos.is_zero() => Some(()),
+            _ => None,
+        });
+
+        commands.send(Command::Pause).unwrap();
+        wait_for(&events, |m| matches!(m, AudioMessage::Paused).then_some(()));
+        commands.send(Command::Resume).unwrap();
+        wait_for(&events, |m| {
+            matches!(m, AudioMessage::Resumed).then_some(())
+        });
+
+        wait_for(&events, |m| {
+            matches!(m, AudioMessage::EndOfTrack).then_some(())
+        });
+    }
+
+    #[test]
+    fn counts_position_from_seek() {
+        let (commands, events) = spawn(&config(PathBuf::new()));
+        let file = TestFile::new(Duration::from_secs(4));
+
+        commands.send(file.play(Duration::ZERO)).unwrap();
+        wait_for(&events, |m| {
+            matches!(m, AudioMessage::Started { .. }).then_some(())
+        });
+
+        // Positions counted from the start can't get past the seek target
+        // before the track ends.
+        let seek = Duration::from_secs(3);
+        commands.send(Command::Seek(seek)).unwrap();
+        let mut last = wait_for(&events, |m| match m {
+            AudioMessage::Position { pos, .. } if pos >= seek => Some(pos),
+            AudioMessage::EndOfTrack => panic!("Track ended before reaching {seek:?}"),
+            AudioMessage::Error(e) => panic!("{e}"),
+            _ => None,
+        });
+
+        loop {
+            let pos = wait_for(&events, |m| match m {
+                AudioMessage::Position { pos, .. } => Some(Some(pos)),
+                AudioMessage::EndOfTrack => Some(None),
+                _ => None,
+            });
+            let Some(pos) = pos else {
+                break;
+            };
+            assert!(pos >= last && pos <= Duration::from_secs(4), "{pos:?}");
+            last = pos;
+        }
+        assert!(last > seek, "{last:?}");
+    }
+
+    #[test]
+    fn no_underrun_in_time() {
+        // Slowed down, the backend pulls less often than progress is checked.
+        let mut config = config(PathBuf::new());
+        config.audio_backend = BackendKind::Null { speed: 0.25 };
+        config.position_update_interval = Duration::from_millis(20);
+        let (commands, events) = spawn(&config);
+        let file = TestFile::new(Duration::from_millis(100));
+
+        commands.send(file.play(Duration::ZERO)).unwrap();
+        wait_for(&events, |m| match m {
+            AudioMessage::Underrun => panic!("Underrun reported"),
+            AudioMessage::EndOfTrack => Some(()),
+            _ => None,
+        });
+    }
 }
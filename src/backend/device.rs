@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use rodio::cpal::BufferSize;
+use rodio::cpal::traits::HostTrait;
+use rodio::mixer::Mixer;
+use rodio::{DeviceTrait, OutputStream, OutputStreamBuilder};
+
+use super::AudioBackend;
+
+/// Assumed when the host picks the buffer size, hosts rarely buffer more.
+const DEFAULT_BUFFER_PERIOD: Duration = Duration::from_millis(200);
+
+/// Real sound card.
+pub struct DeviceBackend {
+    stream: OutputStream,
+    name: Option<String>,
+}
+
+impl DeviceBackend {
+    /// Opens device with given name or the default one if `name` is `None`.
+    pub fn open(name: Option<&str>) -> Result<Self> {
+        let mut stream = match name {
+            Some(name) => {
+                let device = rodio::cpal::default_host()
+                    .output_devices()?
+                    .find(|d| d.name().is_ok_and(|n| n == name))
+                    .ok_or_else(|| eyre!("Audio device \"{name}\" not found"))?;
+
+                OutputStreamBuilder::from_device(device)?.open_stream_or_fallback()?
+            }
+            None => OutputStreamBuilder::open_default_stream()?,
+        };
+
+        // Default message is printed to stderr and breaks the TUI.
+        stream.log_on_drop(false);
+
+        Ok(DeviceBackend {
+            stream,
+            name: name.map(String::from),
+        })
+    }
+}
+
+impl AudioBackend for DeviceBackend {
+    fn mixer(&self) -> &Mixer {
+        self.stream.mixer()
+    }
+
+    fn buffer_period(&self) -> Duration {
+        let config = self.stream.config();
+        match *config.buffer_size() {
+            BufferSize::Fixed(frames) => {
+                Duration::from_secs_f64(frames as f64 / config.sample_rate() as f64)
+            }
+            BufferSize::Default => DEFAULT_BUFFER_PERIOD,
+        }
+    }
+
+    fn device_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// Names of all output devices of the default host.
+pub fn output_devices() -> Vec<String> {
+    rodio::cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+        .unwrap_or_default()
+}
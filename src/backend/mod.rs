@@ -0,0 +1,121 @@
+mod device;
+mod null;
+mod wav;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+pub use device::{DeviceBackend, output_devices};
+pub use null::NullBackend;
+use rodio::mixer::{Mixer, MixerSource};
+use rodio::{ChannelCount, SampleRate};
+use serde::Deserialize;
+pub use wav::WavBackend;
+
+/// Something [`rodio::Sink`]s can play into.
+pub trait AudioBackend {
+    fn mixer(&self) -> &Mixer;
+
+    /// Longest time between two pulls of samples from the mixer while
+    /// playing.
+    fn buffer_period(&self) -> Duration;
+
+    /// Name of the output device, `None` for default device or non device
+    /// backends.
+    fn device_name(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Which backend the audio thread should start with.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Sound card, selected by `Config::audio_device`.
+    #[default]
+    Device,
+    /// Discards samples. `speed` of 1.0 consumes them in real-time, it has
+    /// to be above zero.
+    Null {
+        #[serde(default = "default_speed")]
+        speed: f32,
+    },
+    /// Writes everything played into a 32-bit float WAV file.
+    Wav {
+        path: PathBuf,
+        #[serde(default = "default_speed")]
+        speed: f32,
+    },
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+const CHANNELS: ChannelCount = 2;
+const SAMPLE_RATE: SampleRate = 44_100;
+
+/// Length of audio pulled from the mixer at once by software backends.
+const CHUNK: Duration = Duration::from_millis(10);
+
+/// Thread draining a mixer for software backends, stopped and joined on
+/// drop so whatever `consume` owns is dropped by then.
+struct Consumer {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Consumer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Pause between chunks for a software backend running at `speed`.
+///
+/// With `speed` of 1.0 chunks are pulled at the rate a sound card would pull
+/// them, bigger values speed playback up. Without a pause the consumer
+/// thread would spin, so `speed` has to be above zero.
+fn chunk_pause(speed: f32) -> Result<Duration> {
+    if speed > 0.0 && speed.is_finite() {
+        Ok(CHUNK.div_f32(speed))
+    } else {
+        Err(eyre!("Backend speed must be above zero, got {speed}"))
+    }
+}
+
+/// Spawns a thread which drains `source` in chunks, passing each to
+/// `consume` and sleeping `pause` in between, until the returned
+/// [`Consumer`] is dropped.
+fn spawn_consumer<F>(mut source: MixerSource, pause: Duration, mut consume: F) -> Consumer
+where
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = stop.clone();
+
+    let chunk_len = (SAMPLE_RATE as f32 * CHUNK.as_secs_f32()) as usize * CHANNELS as usize;
+    let thread = std::thread::spawn(move || {
+        let mut chunk = Vec::with_capacity(chunk_len);
+
+        while !stop_flag.load(Ordering::Relaxed) {
+            chunk.clear();
+            chunk.extend(source.by_ref().take(chunk_len));
+            consume(&chunk);
+            std::thread::sleep(pause);
+        }
+    });
+
+    Consumer {
+        stop,
+        thread: Some(thread),
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use rodio::mixer::Mixer;
+
+use super::{AudioBackend, CHANNELS, Consumer, SAMPLE_RATE, chunk_pause, spawn_consumer};
+
+/// Backend without a device, samples are pulled and thrown away.
+pub struct NullBackend {
+    mixer: Mixer,
+    pause: Duration,
+    _consumer: Consumer,
+}
+
+impl NullBackend {
+    pub fn new(speed: f32) -> Result<Self> {
+        let pause = chunk_pause(speed)?;
+        let (mixer, source) = rodio::mixer::mixer(CHANNELS, SAMPLE_RATE);
+        let consumer = spawn_consumer(source, pause, |_| {});
+
+        Ok(NullBackend {
+            mixer,
+            pause,
+            _consumer: consumer,
+        })
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    fn buffer_period(&self) -> Duration {
+        self.pause
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+use std::time::Duration;
+
+use color_eyre::Result;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::mixer::Mixer;
+
+use super::{AudioBackend, CHANNELS, Consumer, SAMPLE_RATE, chunk_pause, spawn_consumer};
+
+/// Backend recording everything played into a WAV file.
+///
+/// File is finalized when the backend is dropped.
+pub struct WavBackend {
+    mixer: Mixer,
+    pause: Duration,
+    _consumer: Consumer,
+}
+
+impl WavBackend {
+    pub fn create(path: &Path, speed: f32) -> Result<Self> {
+        let pause = chunk_pause(speed)?;
+        let spec = WavSpec {
+            channels: CHANNELS,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let mut writer = Some(WavWriter::create(path, spec)?);
+
+        let (mixer, source) = rodio::mixer::mixer(CHANNELS, SAMPLE_RATE);
+        let consumer = spawn_consumer(source, pause, move |chunk| {
+            let Some(w) = writer.as_mut() else {
+                return;
+            };
+
+            // Stop writing on the first error instead of flooding with them.
+            if chunk.iter().try_for_each(|s| w.write_sample(*s)).is_err() {
+                writer = None;
+            }
+        });
+
+        Ok(WavBackend {
+            mixer,
+            pause,
+            _consumer: consumer,
+        })
+    }
+}
+
+impl AudioBackend for WavBackend {
+    fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    fn buffer_period(&self) -> Duration {
+        self.pause
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hound::WavReader;
+    use rodio::buffer::SamplesBuffer;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn writes_every_sample_played() {
+        let path = std::env::temp_dir().join(format!("mood-{}.wav", Uuid::new_v4()));
+        let samples = vec![0.25; SAMPLE_RATE as usize / 10 * CHANNELS as usize];
+
+        let backend = WavBackend::create(&path, 100.0).unwrap();
+        backend
+            .mixer()
+            .add(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples.clone()));
+        std::thread::sleep(Duration::from_millis(200));
+        drop(backend);
+
+        let written = WavReader::open(&path)
+            .unwrap()
+            .into_samples::<f32>()
+            .collect::<Result<Vec<_>, _>>();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written.unwrap(), samples);
+    }
+
+    #[test]
+    fn rejects_speed_without_pause() {
+        let path = std::env::temp_dir().join(format!("mood-{}.wav", Uuid::new_v4()));
+        assert!(WavBackend::create(&path, 0.0).is_err());
+        assert!(WavBackend::create(&path, -1.0).is_err());
+        assert!(!path.exists());
+    }
+}
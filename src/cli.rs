@@ -0,0 +1,402 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::app::{App, Status};
+use crate::audio_thread::AudioThread;
+use crate::config::Config;
+use crate::db;
+use crate::event::{AudioMessage, Event};
+use crate::library;
+use crate::models::Track;
+use crate::playlist_file::{self, PlaylistFormat};
+use crate::stats::{ListeningStats, Period, TopEntry};
+use crate::utils::{canonical, fuzzy_score, spawn_stdin_reader, spawn_ticker};
+
+/// Terminal music player. Starts the TUI unless given a subcommand,
+/// subcommands print JSON for scripts.
+#[derive(Parser)]
+#[command(
+    name = "mood",
+    bin_name = "mood",
+    version,
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    /// Play without the TUI, reading commands like the ones on the
+    /// command line from stdin and printing JSON lines. Exits when stdin is
+    /// closed.
+    #[arg(long)]
+    pub headless: bool,
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Scan the music directory and print library totals.
+    Scan,
+    /// Print tracks in the library.
+    List {
+        /// Fuzzy match against artist, title and album.
+        #[arg(long)]
+        query: Option<String>,
+    },
+    /// Manage playlists.
+    #[command(subcommand)]
+    Playlist(PlaylistCommand),
+    /// Print listening stats.
+    Stats {
+        #[arg(long, value_enum, default_value_t = CliPeriod::Month)]
+        period: CliPeriod,
+    },
+    /// Play files from the library without the TUI, exits when done.
+    Play {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PlaylistCommand {
+    /// Write a playlist to stdout, or to a file.
+    Export {
+        name: String,
+        /// Picked from the extension of `--output`, M3U otherwise.
+        #[arg(long, value_enum)]
+        format: Option<CliFormat>,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CliPeriod {
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl From<CliPeriod> for Period {
+    fn from(period: CliPeriod) -> Self {
+        match period {
+            CliPeriod::Week => Period::Week,
+            CliPeriod::Month => Period::Month,
+            CliPeriod::Year => Period::Year,
+            CliPeriod::All => Period::AllTime,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CliFormat {
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl From<CliFormat> for PlaylistFormat {
+    fn from(format: CliFormat) -> Self {
+        match format {
+            CliFormat::M3u => PlaylistFormat::M3u,
+            CliFormat::Pls => PlaylistFormat::Pls,
+            CliFormat::Xspf => PlaylistFormat::Xspf,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TrackJson<'a> {
+    uuid: String,
+    path: &'a Path,
+    title: String,
+    artist: Option<&'a str>,
+    album: Option<&'a str>,
+    track_number: Option<u32>,
+    year: Option<u32>,
+    genre: Option<&'a str>,
+    /// In seconds.
+    duration: f64,
+    rating: u8,
+    favorite: bool,
+    play_count: u32,
+    skip_count: u32,
+    /// Unix timestamp.
+    last_played: Option<u64>,
+}
+
+impl<'a> From<&'a Track> for TrackJson<'a> {
+    fn from(track: &'a Track) -> Self {
+        TrackJson {
+            uuid: track.uuid.to_string(),
+            path: &track.path,
+            title: track.name(),
+            artist: track.tags.artist.as_deref(),
+            album: track.tags.album.as_deref(),
+            track_number: track.tags.track_number,
+            year: track.tags.year,
+            genre: track.tags.genre.as_deref(),
+            duration: track.duration.as_secs_f64(),
+            rating: track.rating,
+            favorite: track.favorite,
+            play_count: track.stats.play_count,
+            skip_count: track.stats.skip_count,
+            last_played: track
+                .stats
+                .last_played
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TopJson<'a> {
+    name: &'a str,
+    count: u32,
+    uuid: Option<String>,
+}
+
+impl<'a> From<&'a TopEntry> for TopJson<'a> {
+    fn from(entry: &'a TopEntry) -> Self {
+        TopJson {
+            name: &entry.name,
+            count: entry.count,
+            uuid: entry.track.map(|u| u.to_string()),
+        }
+    }
+}
+
+/// Runs a subcommand, or headless mode.
+pub fn run(cli: Cli, config: Config, sqlite: Connection) -> Result<()> {
+    let Some(command) = cli.command else {
+        return run_headless(config, sqlite, None);
+    };
+
+    db::init(&sqlite)?;
+    match command {
+        CliCommand::Scan => {
+            let tracks = library::load(&config.audio_dir, &sqlite)?;
+            print(&json!({
+                "audio_dir": config.audio_dir,
+                "tracks": tracks.len(),
+                "removed": db::removed_tracks(&sqlite)?.len(),
+                "duration": tracks.iter().map(|t| t.duration).sum::<Duration>().as_secs_f64(),
+                "untagged": tracks.iter().filter(|t| t.tags.title.is_none()).count(),
+            }))
+        }
+        CliCommand::List { query } => {
+            let tracks = library::load(&config.audio_dir, &sqlite)?;
+            let mut matches = tracks
+                .iter()
+                .filter_map(|t| match query.as_deref() {
+                    Some(query) => Some((fuzzy_score(query, &searchable(t))?, t)),
+                    None => Some((0, t)),
+                })
+                .collect::<Vec<_>>();
+            if query.is_some() {
+                matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            } else {
+                matches.sort_by(|(_, a), (_, b)| a.path.cmp(&b.path));
+            }
+            let tracks = matches
+                .into_iter()
+                .map(|(_, t)| TrackJson::from(t))
+                .collect::<Vec<_>>();
+            print(&tracks)
+        }
+        CliCommand::Playlist(PlaylistCommand::Export {
+            name,
+            format,
+            output,
+        }) => {
+            let playlist = db::playlists(&sqlite)?
+                .into_iter()
+                .find(|p| p.name == name)
+                .ok_or_else(|| eyre!("No playlist named \"{name}\""))?;
+            let library = library::load(&config.audio_dir, &sqlite)?;
+            let tracks = playlist
+                .tracks
+                .iter()
+                .filter_map(|&uuid| library.iter().find(|t| t.uuid == uuid))
+                .collect::<Vec<_>>();
+
+            let Some(output) = output else {
+                let format = format.map_or(PlaylistFormat::M3u, PlaylistFormat::from);
+                print!("{}", playlist_file::write(format, &name, &tracks, None));
+                return Ok(());
+            };
+            let format = match format {
+                Some(format) => format.into(),
+                None => PlaylistFormat::from_path(&output).unwrap_or(PlaylistFormat::M3u),
+            };
+            playlist_file::export(&output, format, &name, &tracks)?;
+            print(&json!({
+                "name": name,
+                "path": output,
+                "tracks": tracks.len(),
+                "missing": playlist.tracks.len() - tracks.len(),
+            }))
+        }
+        CliCommand::Stats { period } => {
+            let period = Period::from(period);
+            let library = library::load(&config.audio_dir, &sqlite)?;
+            let history = db::history(&sqlite, period.since())?;
+            let stats = ListeningStats::compute(&history, &library);
+            print(&json!({
+                "period": period.name(),
+                "plays": history.len(),
+                "total_listened": stats.total_listened.as_secs_f64(),
+                "top_tracks": top(&stats.top_tracks),
+                "top_artists": top(&stats.top_artists),
+                "top_albums": top(&stats.top_albums),
+                "most_skipped": top(&stats.most_skipped),
+                "per_day": stats
+                    .per_day
+                    .iter()
+                    .map(|(day, listened)| (*day, listened.as_secs_f64()))
+                    .collect::<BTreeMap<_, _>>(),
+            }))
+        }
+        CliCommand::Play { paths } => run_headless(config, sqlite, Some(paths)),
+    }
+}
+
+/// Player without a terminal. Lines on stdin run as commands, each answered
+/// with a JSON line, and every track change is printed as one. With
+/// `play`, exits once those tracks have played.
+fn run_headless(config: Config, sqlite: Connection, play: Option<Vec<PathBuf>>) -> Result<()> {
+    let (event_tx, event_rx) = crossbeam_channel::unbounded();
+    let (command_tx, command_rx) = crossbeam_channel::unbounded();
+
+    spawn_ticker(event_tx.clone(), config.tick_rate);
+    spawn_stdin_reader(event_tx.clone());
+    AudioThread::new(command_rx, event_tx, &config).run()?;
+
+    let exit_when_done = play.is_some();
+    let mut app = if exit_when_done {
+        App::without_session(command_tx, config, sqlite)?
+    } else {
+        App::new(command_tx, config, sqlite)?
+    };
+    app.set_headless(true);
+
+    if let Some(paths) = play {
+        let by_path = app
+            .library()
+            .iter()
+            .map(|t| (canonical(&t.path), t.uuid))
+            .collect::<HashMap<_, _>>();
+        let mut tracks = vec![];
+        for path in paths {
+            match by_path.get(&canonical(&path)) {
+                Some(&uuid) => tracks.push(uuid),
+                None => eprintln!("{}", json!({ "error": "Not in the library", "path": path })),
+            }
+        }
+        if tracks.is_empty() {
+            return Err(eyre!("None of the files are in the library"));
+        }
+        app.play_tracks(tracks)?;
+    }
+
+    let mut playing = app.status().track;
+    print_playing(&app, playing)?;
+
+    loop {
+        match event_rx.recv()? {
+            Event::Tick => app.tick()?,
+            Event::Audio(audio) => {
+                let ended = matches!(audio, AudioMessage::EndOfTrack);
+                app.audio(audio)?;
+                if ended && exit_when_done && app.status().track.is_none() {
+                    break;
+                }
+            }
+            Event::Line(line) if line.trim().is_empty() => {}
+            Event::Line(line) => match app.command(&line) {
+                Ok(()) => print(&json!({ "ok": true, "status": status(&app) }))?,
+                Err(error) => print(&json!({ "ok": false, "error": error }))?,
+            },
+            // Given tracks still play to the end without anyone sending
+            // commands.
+            Event::LinesEnd if exit_when_done => {}
+            Event::LinesEnd => break,
+            // Only sent by the terminal.
+            Event::Input(_)
+            | Event::Mouse(_)
+            | Event::Resize
+            | Event::FocusGained
+            | Event::FocusLost => {}
+        }
+
+        if app.should_quit() {
+            break;
+        }
+        if app.status().track != playing {
+            playing = app.status().track;
+            print_playing(&app, playing)?;
+        }
+    }
+
+    if !exit_when_done {
+        app.save_session()?;
+    }
+    app.record_unfinished_play()
+}
+
+fn status(app: &App) -> serde_json::Value {
+    let Status {
+        track,
+        position,
+        duration,
+        paused,
+        volume,
+        shuffle,
+        repeat,
+    } = app.status();
+    json!({
+        "track": track.map(|u| u.to_string()),
+        "position": position.as_secs_f64(),
+        "duration": duration.as_secs_f64(),
+        "paused": paused,
+        "volume": (volume * 100.0).round() as u8,
+        "shuffle": shuffle,
+        "repeat": repeat.name(),
+    })
+}
+
+fn top(entries: &[TopEntry]) -> Vec<TopJson<'_>> {
+    entries.iter().map(TopJson::from).collect()
+}
+
+fn print_playing(app: &App, playing: Option<Uuid>) -> Result<()> {
+    let track = playing
+        .and_then(|uuid| app.library().iter().find(|t| t.uuid == uuid))
+        .map(TrackJson::from);
+    print(&json!({ "event": "playing", "track": track }))
+}
+
+fn print(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
+
+/// What `list --query` matches against.
+fn searchable(track: &Track) -> String {
+    [
+        track.tags.artist.clone().unwrap_or_default(),
+        track.name(),
+        track.tags.album.clone().unwrap_or_default(),
+    ]
+    .join(" ")
+}
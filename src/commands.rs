@@ -0,0 +1,657 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::components::tracklist::{TrackFilter, TrackSort};
+use crate::config::KeyConfig;
+use crate::event::Key;
+use crate::models::MAX_RATING;
+use crate::queue::Repeat;
+use crate::utils::fuzzy_score;
+
+/// Completions offered for track titles at most, best matches first.
+const MAX_TRACK_COMPLETIONS: usize = 50;
+
+/// Command that can be typed on the command line or picked from the
+/// command palette.
+pub struct CommandInfo {
+    pub name: &'static str,
+    /// Shown after the name, `<required>` or `[optional]`.
+    pub args: &'static str,
+    pub description: &'static str,
+    args_kind: Args,
+    parse: fn(&str) -> Result<Action, String>,
+    /// Key doing the same thing, if there is one.
+    pub key: fn(&KeyConfig) -> Option<Key>,
+}
+
+impl CommandInfo {
+    /// Whether the command can't run without arguments.
+    pub fn needs_args(&self) -> bool {
+        self.args.starts_with('<')
+    }
+}
+
+/// What arguments of a command complete to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Args {
+    None,
+    Tracks,
+    Playlist,
+    Sort,
+    Filter,
+    Repeat,
+    Switch,
+}
+
+pub struct Completion {
+    /// Whole line with the word completed.
+    pub line: String,
+    /// Completed word alone, for listing completions.
+    pub word: String,
+}
+
+impl Completion {
+    fn new(before: impl std::fmt::Display, word: &str, after: &str) -> Self {
+        Completion {
+            line: format!("{before}{word}{after}"),
+            word: word.to_string(),
+        }
+    }
+}
+
+pub enum Action {
+    /// Plays best match for the title, or resumes playback without one.
+    Play(Option<String>),
+    Pause,
+    Next,
+    Prev,
+    Seek(Change<Duration>),
+    /// In percent.
+    Volume(Change<u8>),
+    /// Toggles when `None`.
+    Shuffle(Option<bool>),
+    /// Cycles when `None`.
+    Repeat(Option<Repeat>),
+    Sort(TrackSort),
+    Filter(TrackFilter),
+    /// Commands taking a title act on its best match, on the tracklist
+    /// selection without one.
+    Queue(Option<String>),
+    Rate(u8, Option<String>),
+    Favorite(Option<String>),
+    Edit,
+    Undo,
+    /// Exact title, path or UUID rather than a best match, removing can't
+    /// be undone.
+    Remove(Option<String>),
+    Playlist(PlaylistAction, String),
+    Playlists,
+    /// Path of a playlist file.
+    Import(PathBuf),
+    /// Name of the playlist and path of the file to write it to.
+    Export(String, PathBuf),
+    Devices,
+    Browser,
+    Stats,
+    Lyrics,
+    Quit,
+}
+
+/// Absolute or relative change of a value.
+pub enum Change<T> {
+    Set(T),
+    Add(T),
+    Sub(T),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlaylistAction {
+    /// Adds selected tracks, creating the playlist if needed.
+    Add,
+    Play,
+    Delete,
+}
+
+impl PlaylistAction {
+    const ALL: [PlaylistAction; 3] = [
+        PlaylistAction::Add,
+        PlaylistAction::Play,
+        PlaylistAction::Delete,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            PlaylistAction::Add => "add",
+            PlaylistAction::Play => "play",
+            PlaylistAction::Delete => "delete",
+        }
+    }
+}
+
+pub const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "play",
+        args: "[title]",
+        description: "Play track matching title, or resume playback",
+        args_kind: Args::Tracks,
+        parse: |args| Ok(Action::Play((!args.is_empty()).then(|| args.to_string()))),
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "pause",
+        args: "",
+        description: "Toggle pause",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Pause),
+        key: |k| Some(k.pause),
+    },
+    CommandInfo {
+        name: "next",
+        args: "",
+        description: "Skip to next track",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Next),
+        key: |k| Some(k.skip_to_next_audio),
+    },
+    CommandInfo {
+        name: "prev",
+        args: "",
+        description: "Go back to previous track",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Prev),
+        key: |k| Some(k.skip_to_prev_audio),
+    },
+    CommandInfo {
+        name: "seek",
+        args: "<[+|-]time>",
+        description: "Seek to position like 1:30, or by +10 / -10 seconds",
+        args_kind: Args::None,
+        parse: |args| parse_change(args, parse_time).map(Action::Seek),
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "volume",
+        args: "<[+|-]percent>",
+        description: "Set volume, or change it by +5 / -5",
+        args_kind: Args::None,
+        parse: |args| parse_change(args, parse_percent).map(Action::Volume),
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "shuffle",
+        args: "[on|off]",
+        description: "Toggle shuffle",
+        args_kind: Args::Switch,
+        parse: |args| parse_optional(args, parse_switch).map(Action::Shuffle),
+        key: |k| Some(k.shuffle),
+    },
+    CommandInfo {
+        name: "repeat",
+        args: "[off|all|one]",
+        description: "Set or cycle repeat mode",
+        args_kind: Args::Repeat,
+        parse: |args| parse_optional(args, str::parse).map(Action::Repeat),
+        key: |k| Some(k.repeat),
+    },
+    CommandInfo {
+        name: "sort",
+        args: "<order>",
+        description: "Sort tracks",
+        args_kind: Args::Sort,
+        parse: |args| args.parse().map(Action::Sort),
+        key: |k| Some(k.cycle_sort),
+    },
+    CommandInfo {
+        name: "filter",
+        args: "<filter>",
+        description: "Show all tracks, favorites or ones rated at least N stars",
+        args_kind: Args::Filter,
+        parse: |args| args.parse().map(Action::Filter),
+        key: |k| Some(k.cycle_filter),
+    },
+    CommandInfo {
+        name: "queue",
+        args: "[title]",
+        description: "Add selected tracks, or track matching title, to the queue",
+        args_kind: Args::Tracks,
+        parse: |args| Ok(Action::Queue(optional_title(args))),
+        key: |k| Some(k.add_to_manual_queue),
+    },
+    CommandInfo {
+        name: "rate",
+        args: "<stars> [title]",
+        description: "Rate selected tracks, or track matching title, 0 removes rating",
+        args_kind: Args::None,
+        parse: |args| {
+            let (stars, title) = split(args);
+            match stars.parse() {
+                Ok(stars) if stars <= MAX_RATING => {
+                    Ok(Action::Rate(stars, optional_title(title.trim())))
+                }
+                _ => Err(format!("Rating must be from 0 to {MAX_RATING}")),
+            }
+        },
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "favorite",
+        args: "[title]",
+        description: "Toggle favorite of selected tracks, or track matching title",
+        args_kind: Args::Tracks,
+        parse: |args| Ok(Action::Favorite(optional_title(args))),
+        key: |k| Some(k.toggle_favorite),
+    },
+    CommandInfo {
+        name: "edit",
+        args: "",
+        description: "Edit tags of selected tracks",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Edit),
+        key: |k| Some(k.edit_tags),
+    },
+    CommandInfo {
+        name: "undo",
+        args: "",
+        description: "Undo last tag edit",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Undo),
+        key: |k| Some(k.undo_tag_edit),
+    },
+    CommandInfo {
+        name: "remove",
+        args: "[title|path|uuid]",
+        description: "Remove selected tracks, or the track named exactly, from the library",
+        args_kind: Args::Tracks,
+        parse: |args| Ok(Action::Remove(optional_title(args))),
+        key: |k| Some(k.remove_from_library),
+    },
+    CommandInfo {
+        name: "playlist",
+        args: "<add|play|delete> <name>",
+        description: "Add selected tracks to, play or delete a playlist",
+        args_kind: Args::Playlist,
+        parse: parse_playlist,
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "playlists",
+        args: "",
+        description: "Browse playlists",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Playlists),
+        key: |k| Some(k.focus_playlist_popup),
+    },
+    CommandInfo {
+        name: "import",
+        args: "<file>",
+        description: "Import M3U, PLS or XSPF playlist, replacing one with the same name",
+        args_kind: Args::None,
+        parse: |args| parse_file(args).map(Action::Import),
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "export",
+        args: "<name> <file>",
+        description: "Export playlist, format is picked by extension, quote names with spaces",
+        args_kind: Args::None,
+        parse: parse_export,
+        key: |_| None,
+    },
+    CommandInfo {
+        name: "devices",
+        args: "",
+        description: "Pick audio output device",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Devices),
+        key: |k| Some(k.pick_audio_device),
+    },
+    CommandInfo {
+        name: "browser",
+        args: "",
+        description: "Browse artists to narrow the tracklist to",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Browser),
+        key: |k| Some(k.focus_browser),
+    },
+    CommandInfo {
+        name: "stats",
+        args: "",
+        description: "Show listening stats",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Stats),
+        key: |k| Some(k.focus_stats),
+    },
+    CommandInfo {
+        name: "lyrics",
+        args: "",
+        description: "Show lyrics of the playing track",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Lyrics),
+        key: |k| Some(k.focus_lyrics),
+    },
+    CommandInfo {
+        name: "quit",
+        args: "",
+        description: "Quit",
+        args_kind: Args::None,
+        parse: |args| no_args(args, Action::Quit),
+        key: |k| Some(k.quit),
+    },
+];
+
+/// Command by its name or an unambiguous prefix of it.
+pub fn find(name: &str) -> Result<&'static CommandInfo, String> {
+    if let Some(info) = COMMANDS.iter().find(|c| c.name == name) {
+        return Ok(info);
+    }
+
+    let mut matches = COMMANDS.iter().filter(|c| c.name.starts_with(name));
+    match (matches.next(), matches.next()) {
+        (Some(info), None) => Ok(info),
+        (Some(_), Some(_)) => Err(format!("Ambiguous command \"{name}\"")),
+        _ => Err(format!("Unknown command \"{name}\"")),
+    }
+}
+
+pub fn parse(line: &str) -> Result<Action, String> {
+    let (name, args) = split(line.trim());
+    (find(name)?.parse)(args.trim())
+}
+
+/// Ways to complete the last word of `line`.
+pub fn complete(line: &str, tracks: &[String], playlists: &[String]) -> Vec<Completion> {
+    let line = line.trim_start();
+    if !line.contains(' ') {
+        return COMMANDS
+            .iter()
+            .filter(|c| c.name.starts_with(line))
+            .map(|c| Completion::new("", c.name, if c.args.is_empty() { "" } else { " " }))
+            .collect();
+    }
+
+    let (name, args) = split(line);
+    let Ok(info) = find(name) else {
+        return vec![];
+    };
+    let before = format!("{} ", info.name);
+    let prefixed = |options: &[&str]| {
+        options
+            .iter()
+            .filter(|o| o.starts_with(args))
+            .map(|o| Completion::new(&before, o, ""))
+            .collect()
+    };
+
+    match info.args_kind {
+        Args::None => vec![],
+        Args::Tracks => {
+            let mut matches = tracks
+                .iter()
+                .filter_map(|t| Some((fuzzy_score(args, t)?, t)))
+                .collect::<Vec<_>>();
+            matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            matches
+                .into_iter()
+                .take(MAX_TRACK_COMPLETIONS)
+                .map(|(_, t)| Completion::new(&before, t, ""))
+                .collect()
+        }
+        Args::Playlist => match args.split_once(' ') {
+            None => PlaylistAction::ALL
+                .iter()
+                .filter(|a| a.name().starts_with(args))
+                .map(|a| Completion::new(&before, a.name(), " "))
+                .collect(),
+            Some((action, name)) => playlists
+                .iter()
+                .filter(|p| p.starts_with(name.trim_start()))
+                .map(|p| Completion::new(format!("{before}{action} "), p, ""))
+                .collect(),
+        },
+        Args::Sort => prefixed(&TrackSort::ALL.map(TrackSort::name)),
+        Args::Filter => prefixed(&["all", "favorites", "1+", "2+", "3+", "4+", "5+"]),
+        Args::Repeat => prefixed(&["off", "all", "one"]),
+        Args::Switch => prefixed(&["on", "off"]),
+    }
+}
+
+fn split(line: &str) -> (&str, &str) {
+    line.split_once(' ').unwrap_or((line, ""))
+}
+
+fn no_args(args: &str, action: Action) -> Result<Action, String> {
+    if args.is_empty() {
+        Ok(action)
+    } else {
+        Err(format!("Unexpected argument \"{args}\""))
+    }
+}
+
+fn optional_title(args: &str) -> Option<String> {
+    (!args.is_empty()).then(|| args.to_string())
+}
+
+fn parse_optional<T>(
+    args: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+    if args.is_empty() {
+        Ok(None)
+    } else {
+        parse(args).map(Some)
+    }
+}
+
+fn parse_change<T>(
+    args: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Change<T>, String> {
+    if let Some(value) = args.strip_prefix('+') {
+        parse(value).map(Change::Add)
+    } else if let Some(value) = args.strip_prefix('-') {
+        parse(value).map(Change::Sub)
+    } else {
+        parse(args).map(Change::Set)
+    }
+}
+
+/// Parses `ss`, `m:ss` or `h:mm:ss`. Only the first field may go past 59.
+fn parse_time(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid time \"{s}\"");
+    let parts = s.split(':').collect::<Vec<_>>();
+    if s.is_empty() || parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let mut secs = 0u64;
+    for (i, part) in parts.iter().enumerate() {
+        let value = part.parse::<u64>().map_err(|_| invalid())?;
+        if i > 0 && value >= 60 {
+            return Err(invalid());
+        }
+        secs = secs
+            .checked_mul(60)
+            .and_then(|s| s.checked_add(value))
+            .ok_or_else(invalid)?;
+    }
+
+    Ok(Duration::from_secs(secs))
+}
+
+fn parse_percent(s: &str) -> Result<u8, String> {
+    match s.trim_end_matches('%').parse() {
+        Ok(percent) if percent <= 100 => Ok(percent),
+        _ => Err(format!("Volume \"{s}\" is not between 0 and 100")),
+    }
+}
+
+fn parse_switch(s: &str) -> Result<bool, String> {
+    match s {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("Expected on or off, got \"{s}\"")),
+    }
+}
+
+/// Path with a leading `~` expanded to the home directory.
+fn parse_file(args: &str) -> Result<PathBuf, String> {
+    if args.is_empty() {
+        return Err("Missing file".to_string());
+    }
+
+    let home = |rest: &str| dirs::home_dir().map(|home| home.join(rest));
+    let path = match args.strip_prefix('~') {
+        Some("") => home(""),
+        Some(rest) => rest.strip_prefix('/').and_then(home),
+        None => None,
+    };
+    Ok(path.unwrap_or_else(|| PathBuf::from(args)))
+}
+
+/// `<name> <file>`, or `"<name>" <file>` for names with spaces.
+fn parse_export(args: &str) -> Result<Action, String> {
+    let (name, file) = match args.strip_prefix('"') {
+        Some(quoted) => quoted
+            .split_once('"')
+            .ok_or_else(|| "Missing closing quote".to_string())?,
+        None => split(args),
+    };
+    if name.is_empty() {
+        return Err("Missing playlist name".to_string());
+    }
+
+    Ok(Action::Export(name.to_string(), parse_file(file.trim())?))
+}
+
+fn parse_playlist(args: &str) -> Result<Action, String> {
+    let (action, name) = split(args);
+    let action = PlaylistAction::ALL
+        .into_iter()
+        .find(|a| a.name() == action)
+        .ok_or_else(|| format!("Unknown playlist action \"{action}\""))?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Missing playlist name".to_string());
+    }
+
+    Ok(Action::Playlist(action, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn words(completions: Vec<Completion>) -> Vec<String> {
+        completions.into_iter().map(|c| c.word).collect()
+    }
+
+    #[test]
+    fn find_by_prefix() {
+        assert_eq!(find("vol").unwrap().name, "volume");
+        assert_eq!(find("u").unwrap().name, "undo");
+        // Exact names win over longer commands they prefix.
+        assert_eq!(find("playlist").unwrap().name, "playlist");
+
+        let error = |name| find(name).err().unwrap();
+        assert_eq!(error("p"), "Ambiguous command \"p\"");
+        assert_eq!(error("playl"), "Ambiguous command \"playl\"");
+        assert_eq!(error("q"), "Ambiguous command \"q\"");
+        assert_eq!(error("xyz"), "Unknown command \"xyz\"");
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert!(matches!(parse("play"), Ok(Action::Play(None))));
+        assert!(
+            matches!(parse(" play  some song "), Ok(Action::Play(Some(t))) if t == "some song")
+        );
+        assert!(parse("pl some song").is_err());
+        assert!(parse("pause now").is_err());
+        assert!(matches!(
+            parse("vol +5"),
+            Ok(Action::Volume(Change::Add(5)))
+        ));
+        assert!(matches!(
+            parse("vol 50%"),
+            Ok(Action::Volume(Change::Set(50)))
+        ));
+        assert!(parse("vol 101").is_err());
+        assert!(matches!(parse("rate 3"), Ok(Action::Rate(3, None))));
+        assert!(matches!(parse("rate 2 a song"), Ok(Action::Rate(2, Some(t))) if t == "a song"));
+        assert!(parse("rate 6").is_err());
+        assert!(matches!(
+            parse("playlist add Road trip"),
+            Ok(Action::Playlist(PlaylistAction::Add, name)) if name == "Road trip"
+        ));
+        assert!(parse("playlist add").is_err());
+        assert!(matches!(
+            parse(r#"export "Road trip" /tmp/road trip.m3u"#),
+            Ok(Action::Export(name, path))
+                if name == "Road trip" && path == Path::new("/tmp/road trip.m3u")
+        ));
+        assert!(parse(r#"export "Road trip /tmp/a.m3u"#).is_err());
+        assert!(parse("export mix").is_err());
+    }
+
+    #[test]
+    fn expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(parse_file("~/a.m3u").unwrap(), home.join("a.m3u"));
+        assert_eq!(
+            parse_file("~other/a.m3u").unwrap(),
+            Path::new("~other/a.m3u")
+        );
+        assert_eq!(parse_file("/a.m3u").unwrap(), Path::new("/a.m3u"));
+    }
+
+    #[test]
+    fn parse_times() {
+        let secs = |s: &str| parse_time(s).map(|d| d.as_secs());
+        assert_eq!(secs("90"), Ok(90));
+        assert_eq!(secs("1:30"), Ok(90));
+        assert_eq!(secs("1:02:03"), Ok(3723));
+        assert_eq!(secs("120:00"), Ok(7200));
+
+        assert!(secs("").is_err());
+        assert!(secs("1:60").is_err());
+        assert!(secs("1:2:3:4").is_err());
+        assert!(secs("1:-2").is_err());
+        assert!(secs("a:00").is_err());
+        assert!(secs(&format!("{}:00:00", u64::MAX / 60)).is_err());
+        assert!(secs(&format!("{}:00", u64::MAX)).is_err());
+
+        assert!(matches!(
+            parse("seek -10"),
+            Ok(Action::Seek(Change::Sub(d))) if d == Duration::from_secs(10)
+        ));
+    }
+
+    #[test]
+    fn complete_commands() {
+        assert_eq!(
+            words(complete("pl", &[], &[])),
+            ["play", "playlist", "playlists"]
+        );
+        assert_eq!(complete("vol", &[], &[])[0].line, "volume ");
+        assert_eq!(complete("quit", &[], &[])[0].line, "quit");
+
+        let repeat = complete("rep o", &[], &[]);
+        assert_eq!(words(repeat), ["off", "one"]);
+        assert_eq!(complete("rep a", &[], &[])[0].line, "repeat all");
+
+        let playlists = ["Road trip".to_string(), "Rain".to_string()];
+        assert_eq!(words(complete("playlist a", &[], &playlists)), ["add"]);
+        let names = complete("playlist play Ro", &[], &playlists);
+        assert_eq!(names[0].line, "playlist play Road trip");
+        assert_eq!(names.len(), 1);
+
+        let tracks = ["Blue Monday".to_string(), "Black Hole Sun".to_string()];
+        let best = complete("play bhs", &tracks, &[]);
+        assert_eq!(best[0].word, "Black Hole Sun");
+        assert!(complete("play zzz", &tracks, &[]).is_empty());
+        assert!(complete("nope x", &tracks, &[]).is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::{ListMouse, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::models::Track;
+
+/// Artists of the library, picking one narrows the tracklist down to their
+/// tracks. First row brings back the whole library.
+pub struct BrowserComponent {
+    /// Artists sorted by name with their track counts. Tracks without an
+    /// artist are listed under the empty name.
+    artists: Vec<(String, usize)>,
+    tracks: usize,
+    /// Artist the tracklist is narrowed to.
+    shown: Option<String>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    /// Narrows the tracklist to `artist`, or shows all tracks for `None`.
+    ShowArtist {
+        artist: Option<String>,
+    },
+    Close,
+}
+
+impl BrowserComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            artists: vec![],
+            tracks: 0,
+            shown: None,
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    /// Lists artists of `library`, an artist that's gone stops being shown.
+    pub fn set_library(&mut self, library: &[Track]) {
+        let selected = self.selected();
+
+        self.artists.clear();
+        for track in library {
+            let artist = track.tags.artist.as_deref().unwrap_or_default();
+            match self.artists.iter_mut().find(|(a, _)| a == artist) {
+                Some((_, count)) => *count += 1,
+                None => self.artists.push((artist.to_string(), 1)),
+            }
+        }
+        self.artists.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.tracks = library.len();
+
+        if let Some(artist) = &self.shown
+            && !self.artists.iter().any(|(a, _)| a == artist)
+        {
+            self.shown = None;
+        }
+        self.select(selected);
+    }
+
+    /// Artist the tracklist is narrowed to.
+    pub fn shown(&self) -> Option<&str> {
+        self.shown.as_deref()
+    }
+
+    /// Artist under the cursor, `None` on the row for all tracks.
+    fn selected(&self) -> Option<String> {
+        let pos = self.scroll.pos();
+        (pos > 0)
+            .then(|| self.artists.get(pos - 1))
+            .flatten()
+            .map(|(a, _)| a.clone())
+    }
+
+    /// Moves cursor to `artist`'s row, the first one if they're gone.
+    fn select(&self, artist: Option<String>) {
+        let pos = artist
+            .and_then(|artist| self.artists.iter().position(|(a, _)| *a == artist))
+            .map_or(0, |i| i + 1);
+        self.scroll.pos.set(pos);
+        self.scroll
+            .y_offset
+            .set(self.scroll.y_offset.get().min(pos));
+    }
+
+    fn show_selected(&mut self) -> Result<()> {
+        self.shown = self.selected();
+        self.send_command(Command::ShowArtist {
+            artist: self.shown.clone(),
+        })
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::BrowserComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for BrowserComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let area = {
+            let border = Block::bordered()
+                .title(format!("Artists ({})", self.artists.len()))
+                .border_style(self.theme.border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let rows = self.artists.len() + 1;
+        self.scroll.update_area(area, rows);
+
+        let all = (None, format!("All artists ({})", self.tracks));
+        let lines = std::iter::once(all)
+            .chain(self.artists.iter().map(|(artist, count)| {
+                let name = if artist.is_empty() {
+                    "Unknown artist"
+                } else {
+                    artist
+                };
+                (Some(artist.as_str()), format!("{name} ({count})"))
+            }))
+            .skip(self.scroll.y_offset.get())
+            .take(area.height as usize)
+            .map(|(artist, text)| {
+                if artist == self.shown() {
+                    Line::from(Span::styled(text, self.theme.accent))
+                } else {
+                    Line::raw(text)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(area, buf);
+
+        let selection = self.scroll.pos() - self.scroll.y_offset.get();
+        for i in area.x..area.x + area.width {
+            if let Some(c) = buf.cell_mut((i, selection as u16 + area.y)) {
+                c.set_style(self.theme.selection);
+            }
+        }
+    }
+}
+
+impl Component for BrowserComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        let k = &self.key_config;
+
+        if key == Key::Up || key == k.scroll_up {
+            self.scroll.move_up();
+        } else if key == Key::Down || key == k.scroll_down {
+            self.scroll.move_down(self.artists.len() + 1);
+        } else if key == k.play_audio {
+            self.show_selected()?;
+        } else if key == k.close_popup {
+            self.send_command(Command::Close)?;
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        vec![
+            Binding::keys(&[Key::Up, k.scroll_up], "Move up"),
+            Binding::keys(&[Key::Down, k.scroll_down], "Move down"),
+            Binding::new(k.play_audio, "Show the artist's tracks"),
+            Binding::new(k.close_popup, "Close"),
+        ]
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.artists.len() + 1) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            ListMouse::DoubleClicked(_) => {
+                self.show_selected()?;
+                Ok(EventState::Consumed)
+            }
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crossbeam_channel::Receiver;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::{TrackStats, TrackTags};
+
+    fn track(artist: Option<&str>) -> Track {
+        Track {
+            uuid: Uuid::new_v4(),
+            duration: Duration::ZERO,
+            path: PathBuf::from("/music/song.mp3"),
+            tags: TrackTags {
+                artist: artist.map(str::to_string),
+                ..Default::default()
+            },
+            stats: TrackStats::default(),
+            rating: 0,
+            favorite: false,
+        }
+    }
+
+    fn browser(artists: &[Option<&str>]) -> (BrowserComponent, Receiver<ComponentCommand>) {
+        let (app_cmd_tx, app_cmd_rx) = crossbeam_channel::unbounded();
+        let mut browser = BrowserComponent::new(KeyConfig::default(), Theme::dark(), app_cmd_tx);
+        let library = artists.iter().map(|&a| track(a)).collect::<Vec<_>>();
+        browser.set_library(&library);
+        (browser, app_cmd_rx)
+    }
+
+    fn shown(rx: &Receiver<ComponentCommand>) -> Option<String> {
+        match rx.try_recv() {
+            Ok(ComponentCommand::BrowserComponent(Command::ShowArtist { artist })) => artist,
+            _ => panic!("Expected an artist to be shown"),
+        }
+    }
+
+    #[test]
+    fn counts_tracks_per_artist() {
+        let (browser, _) = browser(&[Some("Beta"), None, Some("Alpha"), Some("Beta")]);
+        assert_eq!(
+            browser.artists,
+            [
+                (String::new(), 1),
+                ("Alpha".to_string(), 1),
+                ("Beta".to_string(), 2)
+            ]
+        );
+        assert_eq!(browser.tracks, 4);
+    }
+
+    #[test]
+    fn picks_artist_or_all() {
+        let (mut browser, rx) = browser(&[Some("Alpha"), Some("Beta")]);
+
+        browser.event(Key::Down).unwrap();
+        browser.event(Key::Down).unwrap();
+        browser.event(Key::Enter).unwrap();
+        assert_eq!(shown(&rx).as_deref(), Some("Beta"));
+        assert_eq!(browser.shown(), Some("Beta"));
+
+        browser.event(Key::Char('k')).unwrap();
+        browser.event(Key::Char('k')).unwrap();
+        browser.event(Key::Enter).unwrap();
+        assert_eq!(shown(&rx), None);
+        assert_eq!(browser.shown(), None);
+    }
+
+    #[test]
+    fn forgets_artist_that_is_gone() {
+        let (mut browser, rx) = browser(&[Some("Alpha"), Some("Beta")]);
+        browser.event(Key::Down).unwrap();
+        browser.event(Key::Enter).unwrap();
+        assert_eq!(shown(&rx).as_deref(), Some("Alpha"));
+
+        // Cursor stays on the artist when rows before it come and go.
+        browser.set_library(&[track(None), track(Some("Alpha"))]);
+        assert_eq!(browser.selected().as_deref(), Some("Alpha"));
+        assert_eq!(browser.shown(), Some("Alpha"));
+
+        browser.set_library(&[track(Some("Beta"))]);
+        assert_eq!(browser.selected(), None);
+        assert_eq!(browser.shown(), None);
+    }
+}
@@ -0,0 +1,219 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Clear;
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::commands::{self, Completion};
+use crate::components::utils::TextInput;
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+
+/// `:` command line at the bottom of the screen.
+pub struct CommandLineComponent {
+    input: TextInput,
+    error: Option<String>,
+    completions: Vec<Completion>,
+    /// Completion put into input by the last tab press.
+    completion: Option<usize>,
+    /// Track titles and playlist names to complete arguments with.
+    tracks: Vec<String>,
+    playlists: Vec<String>,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    Run { line: String },
+    Close,
+}
+
+impl CommandLineComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            input: TextInput::new(&theme),
+            error: None,
+            completions: vec![],
+            completion: None,
+            tracks: vec![],
+            playlists: vec![],
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    /// Opens command line with `input` already typed.
+    pub fn open(&mut self, input: &str) {
+        self.input.set_value(input);
+        self.error = None;
+        self.reset_completion();
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    pub fn set_completion_data(&mut self, tracks: Vec<String>, playlists: Vec<String>) {
+        self.tracks = tracks;
+        self.playlists = playlists;
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let line = self.input.value().trim().to_string();
+        if line.is_empty() {
+            return self.send_command(Command::Close);
+        }
+
+        self.input.push_history(line.clone());
+
+        self.send_command(Command::Run { line })
+    }
+
+    /// Replaces input with the next completion, cycling through all of
+    /// them on repeated presses.
+    fn complete(&mut self) {
+        let next = match self.completion {
+            Some(i) => (i + 1) % self.completions.len(),
+            None => {
+                self.completions =
+                    commands::complete(self.input.value(), &self.tracks, &self.playlists);
+                if self.completions.is_empty() {
+                    return;
+                }
+                0
+            }
+        };
+
+        self.input.set_value(self.completions[next].line.clone());
+        // Single completion is final, next tab completes the following word.
+        self.completion = (self.completions.len() > 1).then_some(next);
+    }
+
+    fn reset_completion(&mut self) {
+        self.completions.clear();
+        self.completion = None;
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::CommandLineComponent(cmd))?;
+        Ok(())
+    }
+}
+
+/// Takes the bottom line of the area, and the one above it for completions
+/// when there are several.
+impl WidgetRef for CommandLineComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 {
+            return;
+        }
+        let input_area = Rect {
+            y: area.bottom() - 1,
+            height: 1,
+            ..area
+        };
+
+        Clear.render(input_area, buf);
+        if let Some(error) = self.error.as_ref() {
+            Line::from(vec![
+                Span::raw(format!(":{} ", self.input.value())),
+                Span::styled(error, self.theme.error),
+            ])
+            .render(input_area, buf);
+        } else {
+            let [prompt_area, text_area] =
+                Layout::horizontal([Constraint::Length(1), Constraint::Fill(1)]).areas(input_area);
+            Line::raw(":").render(prompt_area, buf);
+            self.input.render_ref(text_area, buf);
+        }
+
+        let Some(current) = self.completion else {
+            return;
+        };
+        if area.height < 2 {
+            return;
+        }
+        let completions_area = Rect {
+            y: input_area.y - 1,
+            ..input_area
+        };
+
+        let words = self
+            .completions
+            .iter()
+            .map(|c| c.word.as_str())
+            .collect::<Vec<_>>();
+
+        // Scrolls so the current completion is visible.
+        let mut start = current;
+        let mut width = words[current].chars().count() + 2;
+        while start > 0 && width + words[start - 1].chars().count() + 2 <= area.width as usize {
+            start -= 1;
+            width += words[start].chars().count() + 2;
+        }
+
+        let spans = words
+            .iter()
+            .enumerate()
+            .skip(start)
+            .flat_map(|(i, word)| {
+                let style = if i == current {
+                    self.theme.selection
+                } else {
+                    self.theme.dim
+                };
+                [Span::styled(*word, style), Span::raw("  ")]
+            })
+            .collect::<Vec<_>>();
+
+        Clear.render(completions_area, buf);
+        Line::from(spans).render(completions_area, buf);
+    }
+}
+
+impl Component for CommandLineComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.confirm {
+            self.run()?;
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.complete {
+            self.complete();
+            return Ok(EventState::Consumed);
+        } else if key == self.key_config.history_prev {
+            self.input.history_prev();
+        } else if key == self.key_config.history_next {
+            self.input.history_next();
+        } else if key == Key::Backspace && self.input.value().is_empty() {
+            // Deleting past the start leaves command line, like in vim.
+            self.send_command(Command::Close)?;
+        } else if !self.input.event(key).is_consumed() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        self.error = None;
+        self.reset_completion();
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        vec![
+            Binding::new(k.confirm, "Run command"),
+            Binding::new(k.complete, "Complete command or argument"),
+            Binding::new(k.history_prev, "Previous command"),
+            Binding::new(k.history_next, "Next command"),
+            Binding::new(k.close_popup, "Cancel"),
+        ]
+        .into_iter()
+        .chain(TextInput::bindings())
+        .collect()
+    }
+}
@@ -0,0 +1,211 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Paragraph};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::commands::{COMMANDS, CommandInfo};
+use crate::components::utils::{ListMouse, TextInput, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::utils::fuzzy_score;
+
+/// Every command with its key binding, fuzzy searched by typing.
+pub struct CommandPaletteComponent {
+    query: TextInput,
+    /// Commands matching `query`, best first.
+    matches: Vec<&'static CommandInfo>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    /// Runs command that needs no arguments.
+    Run {
+        line: String,
+    },
+    /// Opens command line to type arguments of the command.
+    Prompt {
+        line: String,
+    },
+    Close,
+}
+
+impl CommandPaletteComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        let mut palette = Self {
+            query: TextInput::new(&theme).with_placeholder("Type to search commands"),
+            matches: vec![],
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        };
+        palette.search();
+        palette
+    }
+
+    pub fn open(&mut self) {
+        self.query.clear();
+        self.search();
+    }
+
+    /// Matches description too, so commands can be found without knowing
+    /// their names.
+    fn search(&mut self) {
+        let mut matches = COMMANDS
+            .iter()
+            .filter_map(|c| {
+                let score = fuzzy_score(self.query.value(), c.name)
+                    .max(fuzzy_score(self.query.value(), c.description))?;
+                Some((score, c))
+            })
+            .collect::<Vec<_>>();
+        // Stable, commands keep registry order when scores tie.
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        self.matches = matches.into_iter().map(|(_, c)| c).collect();
+        self.scroll = VerticalScroll::new();
+    }
+
+    fn pick(&mut self) -> Result<()> {
+        let Some(info) = self.matches.get(self.scroll.pos()) else {
+            return Ok(());
+        };
+
+        let line = info.name.to_string();
+        if info.needs_args() {
+            self.send_command(Command::Prompt { line: line + " " })
+        } else {
+            self.send_command(Command::Run { line })
+        }
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::CommandPaletteComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for CommandPaletteComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let border = Block::bordered()
+                .title("Commands")
+                .border_style(self.theme.focused_border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let [query_area, list_area] = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(1), Constraint::Fill(1)],
+        )
+        .areas(area);
+
+        let [prompt_area, query_area] =
+            Layout::horizontal([Constraint::Length(2), Constraint::Fill(1)]).areas(query_area);
+        Line::raw("> ").render(prompt_area, buf);
+        self.query.render_ref(query_area, buf);
+
+        self.scroll.update_area(list_area, self.matches.len());
+
+        let name_width = COMMANDS
+            .iter()
+            .map(|c| c.name.len() + c.args.len() + 1)
+            .max()
+            .unwrap_or_default();
+        let shown = self
+            .matches
+            .iter()
+            .skip(self.scroll.y_offset.get())
+            .take(list_area.height as usize);
+        let lines = shown
+            .clone()
+            .map(|c| {
+                let usage = format!("{} {}", c.name, c.args);
+                Line::from(vec![
+                    Span::raw(format!("{usage:name_width$}  ")),
+                    Span::styled(c.description, self.theme.dim),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let keys = shown
+            .map(|c| match (c.key)(&self.key_config) {
+                Some(key) => Line::raw(format!(" {key} ")).right_aligned(),
+                None => Line::default(),
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(list_area, buf);
+        Paragraph::new(keys).render(list_area, buf);
+
+        if !self.matches.is_empty() {
+            let selection = self.scroll.pos() - self.scroll.y_offset.get();
+            for i in list_area.x..list_area.x + list_area.width {
+                if let Some(c) = buf.cell_mut((i, selection as u16 + list_area.y)) {
+                    c.set_style(self.theme.selection);
+                }
+            }
+        }
+    }
+}
+
+impl Component for CommandPaletteComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+        } else if key == self.key_config.confirm {
+            self.pick()?;
+        } else if key == Key::Up {
+            self.scroll.move_up();
+        } else if key == Key::Down {
+            self.scroll.move_down(self.matches.len());
+        } else if self.query.event(key).is_consumed() {
+            self.search();
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(Key::Up, "Move up"),
+            Binding::new(Key::Down, "Move down"),
+            Binding::new(self.key_config.confirm, "Run command"),
+            Binding::new(self.key_config.close_popup, "Close"),
+        ]
+        .into_iter()
+        .chain(TextInput::bindings())
+        .collect()
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.matches.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            ListMouse::DoubleClicked(_) => {
+                self.pick()?;
+                Ok(EventState::Consumed)
+            }
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+
+/// Asks before doing something that can't be undone. Sends the wrapped
+/// command only if the user agrees.
+pub struct ConfirmComponent {
+    message: String,
+    on_confirm: Option<ComponentCommand>,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    Close,
+}
+
+impl ConfirmComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            message: String::new(),
+            on_confirm: None,
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn open(&mut self, message: String, on_confirm: ComponentCommand) {
+        self.message = message;
+        self.on_confirm = Some(on_confirm);
+    }
+
+    fn confirm(&mut self) -> Result<()> {
+        self.send_command(Command::Close)?;
+        if let Some(cmd) = self.on_confirm.take() {
+            self.app_cmd_tx.send(cmd)?;
+        }
+        Ok(())
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::ConfirmComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for ConfirmComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Length(5)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let border = Block::bordered()
+                .title("Confirm")
+                .border_style(self.theme.warning);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let hint = format!(
+            "y/{}: yes   n/{}: no",
+            self.key_config.confirm, self.key_config.close_popup
+        );
+        Paragraph::new(vec![
+            Line::raw(self.message.as_str()),
+            Line::default(),
+            Line::styled(hint, self.theme.dim),
+        ])
+        .wrap(Wrap { trim: true })
+        .render(area, buf);
+    }
+}
+
+/// Modal, every key is consumed so nothing happens behind it.
+impl Component for ConfirmComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.confirm || key == Key::Char('y') {
+            self.confirm()?;
+        } else if key == self.key_config.close_popup || key == Key::Char('n') {
+            self.send_command(Command::Close)?;
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::keys(&[self.key_config.confirm, Key::Char('y')], "Yes"),
+            Binding::keys(&[self.key_config.close_popup, Key::Char('n')], "No"),
+        ]
+    }
+}
@@ -0,0 +1,355 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, RgbImage};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::text::Line;
+use ratatui::widgets::{Widget, WidgetRef};
+use serde::Deserialize;
+
+use crate::config::Theme;
+use crate::io::read_cover;
+
+/// Decoded covers kept around, cache is dropped whole when it grows past
+/// this.
+const CACHE_SIZE: usize = 64;
+/// Cell size assumed when the terminal doesn't report its size in pixels.
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+/// Kitty limits size of a single graphics escape payload.
+const KITTY_CHUNK: usize = 4096;
+
+/// How cover images are drawn.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsProtocol {
+    /// Two pixels per cell using `▀` with truecolor foreground and
+    /// background, works almost everywhere.
+    HalfBlocks,
+    Sixel,
+    Kitty,
+    None,
+}
+
+impl GraphicsProtocol {
+    /// Guesses the best protocol from environment variables terminals set.
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default().to_lowercase();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "wezterm"
+            || program == "ghostty"
+        {
+            GraphicsProtocol::Kitty
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.starts_with("contour")
+        {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::HalfBlocks
+        }
+    }
+
+    fn is_graphics(self) -> bool {
+        matches!(self, GraphicsProtocol::Sixel | GraphicsProtocol::Kitty)
+    }
+}
+
+/// Cover of the playing track.
+///
+/// Sixel and kitty images can't go through ratatui's buffer, cells under
+/// them are skipped while rendering and the image itself is written straight
+/// to the terminal by [`CoverArtComponent::graphics`] after the frame is
+/// drawn.
+pub struct CoverArtComponent {
+    protocol: GraphicsProtocol,
+    cell_size: (u32, u32),
+    image: Option<Arc<DynamicImage>>,
+    cache: HashMap<PathBuf, Option<Arc<DynamicImage>>>,
+    /// Image scaled for the last rendered area.
+    scaled: RefCell<Option<(Rect, RgbImage)>>,
+    /// Area the image was last rendered to and whether the terminal has to
+    /// be sent the image again.
+    area: Cell<Rect>,
+    dirty: Cell<bool>,
+    theme: Theme,
+}
+
+impl CoverArtComponent {
+    pub fn new(protocol: GraphicsProtocol, theme: Theme) -> Self {
+        let cell_size = crossterm::terminal::window_size()
+            .ok()
+            .filter(|s| s.width > 0 && s.height > 0 && s.columns > 0 && s.rows > 0)
+            .map(|s| ((s.width / s.columns) as u32, (s.height / s.rows) as u32))
+            .unwrap_or(DEFAULT_CELL_SIZE);
+
+        Self {
+            protocol,
+            cell_size,
+            image: None,
+            cache: HashMap::new(),
+            scaled: RefCell::new(None),
+            area: Cell::new(Rect::default()),
+            dirty: Cell::new(false),
+            theme,
+        }
+    }
+
+    /// Shows cover of the track at `path`, decoding it unless it's cached.
+    pub fn set_track(&mut self, path: Option<&Path>) {
+        let image = path.and_then(|path| {
+            if self.cache.len() >= CACHE_SIZE {
+                self.cache.clear();
+            }
+            self.cache
+                .entry(path.to_path_buf())
+                .or_insert_with(|| {
+                    read_cover(path)
+                        .and_then(|data| image::load_from_memory(&data).ok())
+                        .map(Arc::new)
+                })
+                .clone()
+        });
+
+        let changed = match (self.image.as_ref(), image.as_ref()) {
+            (Some(a), Some(b)) => !Arc::ptr_eq(a, b),
+            (None, None) => false,
+            _ => true,
+        };
+        if changed {
+            self.image = image;
+            self.scaled.replace(None);
+            self.dirty.set(true);
+        }
+    }
+
+    pub fn invalidate(&self) {
+        self.dirty.set(true);
+    }
+
+    /// Escape sequence drawing the image and where to write it, `None` when
+    /// the terminal is already up to date.
+    pub fn graphics(&self) -> Option<(Rect, String)> {
+        if !self.protocol.is_graphics() || !self.dirty.replace(false) {
+            return None;
+        }
+
+        let area = self.area.get();
+        let mut seq = String::new();
+        if self.protocol == GraphicsProtocol::Kitty {
+            // Delete previously placed images.
+            seq.push_str("\x1b_Ga=d,d=A,q=2\x1b\\");
+        }
+
+        let Some(image) = self.image.as_ref() else {
+            return Some((area, seq));
+        };
+        if area.is_empty() {
+            return Some((area, seq));
+        }
+
+        let (cw, ch) = self.cell_size;
+        let scaled = image
+            .resize(
+                area.width as u32 * cw,
+                area.height as u32 * ch,
+                FilterType::Triangle,
+            )
+            .to_rgb8();
+
+        match self.protocol {
+            GraphicsProtocol::Kitty => {
+                let cols = scaled.width().div_ceil(cw);
+                let rows = scaled.height().div_ceil(ch);
+                let mut png = Vec::new();
+                if DynamicImage::ImageRgb8(scaled)
+                    .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                    .is_err()
+                {
+                    return None;
+                }
+                seq.push_str(&kitty(&png, cols, rows));
+            }
+            GraphicsProtocol::Sixel => seq.push_str(&sixel(&scaled)),
+            _ => unreachable!(),
+        }
+
+        Some((area, seq))
+    }
+
+    fn render_half_blocks(&self, area: Rect, buf: &mut Buffer) {
+        let Some(image) = self.image.as_ref() else {
+            return;
+        };
+
+        let mut scaled = self.scaled.borrow_mut();
+        if scaled.as_ref().is_none_or(|(a, _)| *a != area) {
+            let image = image
+                .resize(
+                    area.width as u32,
+                    area.height as u32 * 2,
+                    FilterType::Triangle,
+                )
+                .to_rgb8();
+            *scaled = Some((area, image));
+        }
+        let Some((_, image)) = scaled.as_ref() else {
+            return;
+        };
+
+        // Center the image in the area.
+        let x_offset = (area.width as u32 - image.width()) / 2;
+        let y_offset = (area.height as u32 * 2 - image.height()) / 4;
+
+        for y in 0..image.height().div_ceil(2) {
+            for x in 0..image.width() {
+                let pos = (
+                    area.x + (x + x_offset) as u16,
+                    area.y + (y + y_offset) as u16,
+                );
+                let Some(cell) = buf.cell_mut(pos) else {
+                    continue;
+                };
+
+                let [r, g, b] = image.get_pixel(x, y * 2).0;
+                cell.set_char('▀')
+                    .set_fg(self.theme.color(Color::Rgb(r, g, b)));
+                if y * 2 + 1 < image.height() {
+                    let [r, g, b] = image.get_pixel(x, y * 2 + 1).0;
+                    cell.set_bg(self.theme.color(Color::Rgb(r, g, b)));
+                }
+            }
+        }
+    }
+}
+
+impl WidgetRef for CoverArtComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        if self.protocol == GraphicsProtocol::None {
+            return;
+        }
+        // Also when the cover gets hidden, so the image is cleared.
+        if self.protocol.is_graphics() && self.area.replace(area) != area {
+            self.dirty.set(true);
+        }
+        if area.is_empty() {
+            return;
+        }
+
+        if self.image.is_none() {
+            let y = area.y + area.height / 2;
+            Line::raw("♪").centered().render(
+                Rect {
+                    y,
+                    height: 1,
+                    ..area
+                },
+                buf,
+            );
+        }
+
+        if self.protocol.is_graphics() {
+            if self.image.is_some() {
+                for y in area.top()..area.bottom() {
+                    for x in area.left()..area.right() {
+                        if let Some(cell) = buf.cell_mut((x, y)) {
+                            cell.set_skip(true);
+                        }
+                    }
+                }
+            }
+        } else {
+            self.render_half_blocks(area, buf);
+        }
+    }
+}
+
+/// Transmits and places PNG in one go, split into chunks kitty accepts.
+fn kitty(png: &[u8], cols: u32, rows: u32) -> String {
+    let data = BASE64.encode(png);
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK).collect::<Vec<_>>();
+
+    let mut seq = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap();
+        if i == 0 {
+            _ = write!(
+                seq,
+                "\x1b_Gf=100,a=T,q=2,C=1,c={cols},r={rows},m={more};{chunk}\x1b\\"
+            );
+        } else {
+            _ = write!(seq, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    seq
+}
+
+/// Encodes image as sixels using a fixed 6x6x6 color cube palette.
+fn sixel(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let index = |x, y| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        let level = |c: u8| (c as usize * 5 + 127) / 255;
+        level(r) * 36 + level(g) * 6 + level(b)
+    };
+
+    let mut seq = format!("\x1bPq\"1;1;{width};{height}");
+    for i in 0..216 {
+        let percent = |level: usize| level * 100 / 5;
+        _ = write!(
+            seq,
+            "#{i};2;{};{};{}",
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = (band..(band + 6).min(height)).collect::<Vec<_>>();
+
+        // Sixel bits of every color present in the band, per column.
+        let mut colors: HashMap<usize, Vec<u8>> = HashMap::new();
+        for x in 0..width {
+            for (bit, &y) in rows.iter().enumerate() {
+                colors
+                    .entry(index(x, y))
+                    .or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << bit;
+            }
+        }
+
+        for (i, (color, bits)) in colors.iter().enumerate() {
+            if i > 0 {
+                seq.push('$');
+            }
+            _ = write!(seq, "#{color}");
+
+            for run in bits.chunk_by(|a, b| a == b) {
+                let c = char::from(0x3f + run[0]);
+                match run.len() {
+                    n if n > 3 => _ = write!(seq, "!{n}{c}"),
+                    n => seq.extend(std::iter::repeat_n(c, n)),
+                }
+            }
+        }
+        seq.push('-');
+    }
+
+    seq.push_str("\x1b\\");
+    seq
+}
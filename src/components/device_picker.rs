@@ -0,0 +1,155 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::widgets::{Block, Clear, Paragraph};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::{ListMouse, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::EventState;
+
+pub struct DevicePickerComponent {
+    devices: Vec<String>,
+    current: Option<String>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    SelectDevice { name: String },
+    Close,
+}
+
+impl DevicePickerComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            devices: vec![],
+            current: None,
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn set_devices(&mut self, devices: Vec<String>) {
+        self.devices = devices;
+        self.scroll = VerticalScroll::new();
+    }
+
+    pub fn set_current(&mut self, current: Option<String>) {
+        self.current = current;
+    }
+
+    fn select(&self) -> Result<()> {
+        if let Some(name) = self.devices.get(self.scroll.pos()) {
+            self.send_command(Command::SelectDevice { name: name.clone() })?;
+        }
+        Ok(())
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::DevicePickerComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for DevicePickerComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let border = Block::bordered()
+                .title("Audio device")
+                .border_style(self.theme.focused_border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        if self.devices.is_empty() {
+            Paragraph::new("No output devices found").render(area, buf);
+            return;
+        }
+
+        self.scroll.update_area(area, self.devices.len());
+
+        let devices = self
+            .devices
+            .iter()
+            .skip(self.scroll.y_offset.get())
+            .take(area.height as usize)
+            .map(|d| {
+                if self.current.as_ref() == Some(d) {
+                    format!("* {d}")
+                } else {
+                    format!("  {d}")
+                }
+            })
+            .collect::<Vec<String>>();
+
+        Paragraph::new(devices.join("\n")).render(area, buf);
+
+        let selection = self.scroll.pos() - self.scroll.y_offset.get();
+        for i in area.x..area.x + area.width {
+            if let Some(c) = buf.cell_mut((i, selection as u16 + area.y)) {
+                c.set_style(self.theme.selection);
+            }
+        }
+    }
+}
+
+impl Component for DevicePickerComponent {
+    fn event(&mut self, key: crate::event::Key) -> Result<EventState> {
+        if key == self.key_config.scroll_up {
+            self.scroll.move_up();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.scroll_down {
+            if !self.devices.is_empty() {
+                self.scroll.move_down(self.devices.len());
+            }
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.play_audio {
+            self.select()?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+            Ok(EventState::Consumed)
+        } else {
+            Ok(EventState::NotConsumed)
+        }
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        vec![
+            Binding::new(k.scroll_up, "Move up"),
+            Binding::new(k.scroll_down, "Move down"),
+            Binding::new(k.play_audio, "Switch to device"),
+            Binding::new(k.close_popup, "Close"),
+        ]
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.devices.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            ListMouse::DoubleClicked(_) => {
+                self.select()?;
+                Ok(EventState::Consumed)
+            }
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
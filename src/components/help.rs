@@ -0,0 +1,227 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Paragraph};
+
+use super::{Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::{ListMouse, TextInput, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::utils::fuzzy_score;
+
+/// Keys doing one action, shown in help.
+pub struct Binding {
+    pub keys: Vec<Key>,
+    pub description: &'static str,
+}
+
+impl Binding {
+    pub fn new(key: Key, description: &'static str) -> Self {
+        Binding {
+            keys: vec![key],
+            description,
+        }
+    }
+
+    /// Same action on several keys, duplicates are shown once.
+    pub fn keys(keys: &[Key], description: &'static str) -> Self {
+        let mut unique = vec![];
+        for key in keys {
+            if !unique.contains(key) {
+                unique.push(*key);
+            }
+        }
+        Binding {
+            keys: unique,
+            description,
+        }
+    }
+
+    fn keys_name(&self) -> String {
+        self.keys
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Bindings of the focused component followed by ones that work
+/// everywhere, filtered by typing.
+pub struct HelpComponent {
+    sections: Vec<(&'static str, Vec<Binding>)>,
+    query: TextInput,
+    /// Section headers and bindings matching `query`, as shown.
+    rows: Vec<Row>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+enum Row {
+    Section(&'static str),
+    /// Indices into `sections`.
+    Binding(usize, usize),
+}
+
+pub enum Command {
+    Close,
+}
+
+impl HelpComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            sections: vec![],
+            query: TextInput::new(&theme).with_placeholder("Type to search"),
+            rows: vec![],
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn set_sections(&mut self, sections: Vec<(&'static str, Vec<Binding>)>) {
+        self.sections = sections;
+        self.query.clear();
+        self.search();
+    }
+
+    /// Matches descriptions and key names, sections without matches are
+    /// left out.
+    fn search(&mut self) {
+        self.rows.clear();
+        let query = self.query.value();
+        for (i, (name, bindings)) in self.sections.iter().enumerate() {
+            let matches = bindings
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| {
+                    query.is_empty()
+                        || fuzzy_score(query, b.description).is_some()
+                        || b.keys_name().eq_ignore_ascii_case(query)
+                })
+                .map(|(j, _)| Row::Binding(i, j))
+                .collect::<Vec<_>>();
+
+            if !matches.is_empty() {
+                self.rows.push(Row::Section(name));
+                self.rows.extend(matches);
+            }
+        }
+        self.scroll = VerticalScroll::new();
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx.send(ComponentCommand::HelpComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for HelpComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let border = Block::bordered()
+                .title("Help")
+                .border_style(self.theme.focused_border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let [query_area, list_area] = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(1), Constraint::Fill(1)],
+        )
+        .areas(area);
+
+        self.query.render_ref(query_area, buf);
+
+        self.scroll.update_area(list_area, self.rows.len());
+
+        let keys_width = self
+            .sections
+            .iter()
+            .flat_map(|(_, bindings)| bindings.iter().map(|b| b.keys_name().chars().count()))
+            .max()
+            .unwrap_or_default();
+        let lines = self
+            .rows
+            .iter()
+            .skip(self.scroll.y_offset.get())
+            .take(list_area.height as usize)
+            .map(|row| match *row {
+                Row::Section(name) => Line::styled(name, self.theme.heading),
+                Row::Binding(i, j) => {
+                    let binding = &self.sections[i].1[j];
+                    Line::from(vec![
+                        Span::styled(
+                            format!("  {:>keys_width$}", binding.keys_name()),
+                            self.theme.accent,
+                        ),
+                        Span::raw(format!("  {}", binding.description)),
+                    ])
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(list_area, buf);
+
+        if !self.rows.is_empty() {
+            let selection = self.scroll.pos() - self.scroll.y_offset.get();
+            for i in list_area.x..list_area.x + list_area.width {
+                if let Some(c) = buf.cell_mut((i, selection as u16 + list_area.y)) {
+                    c.set_style(self.theme.selection);
+                }
+            }
+        }
+    }
+}
+
+impl Component for HelpComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.close_popup
+            || (key == self.key_config.help && self.query.value().is_empty())
+        {
+            self.send_command(Command::Close)?;
+        } else if key == Key::Up {
+            self.scroll.move_up();
+        } else if key == Key::Down {
+            self.scroll.move_down(self.rows.len());
+        } else if key == Key::PageUp {
+            for _ in 0..self.scroll.page_height() {
+                self.scroll.move_up();
+            }
+        } else if key == Key::PageDown {
+            for _ in 0..self.scroll.page_height() {
+                self.scroll.move_down(self.rows.len());
+            }
+        } else if self.query.event(key).is_consumed() {
+            self.search();
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.rows.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::widgets::{Block, Clear};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::TextInput;
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+
+/// Builds the command to send from the submitted text.
+pub type OnSubmit = Box<dyn FnOnce(String) -> ComponentCommand + Send + Sync>;
+
+/// Asks for a line of text, e.g. a name for a new playlist.
+pub struct InputComponent {
+    title: String,
+    input: TextInput,
+    on_submit: Option<OnSubmit>,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    Close,
+}
+
+impl InputComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            title: String::new(),
+            input: TextInput::new(&theme),
+            on_submit: None,
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn open(&mut self, title: String, value: String, placeholder: String, on_submit: OnSubmit) {
+        self.title = title;
+        self.input.set_value(value);
+        self.input.set_placeholder(placeholder);
+        self.on_submit = Some(on_submit);
+    }
+
+    /// Empty input isn't submitted.
+    fn submit(&mut self) -> Result<()> {
+        let value = self.input.value().trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        self.send_command(Command::Close)?;
+        if let Some(on_submit) = self.on_submit.take() {
+            self.app_cmd_tx.send(on_submit(value.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::InputComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for InputComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Length(3)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let border = Block::bordered()
+                .title(self.title.as_str())
+                .border_style(self.theme.focused_border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        self.input.render_ref(area, buf);
+    }
+}
+
+impl Component for InputComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.confirm {
+            self.submit()?;
+        } else if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+        } else if !self.input.event(key).is_consumed() {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(self.key_config.confirm, "Submit"),
+            Binding::new(self.key_config.close_popup, "Cancel"),
+        ]
+        .into_iter()
+        .chain(TextInput::bindings())
+        .collect()
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::{ListMouse, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::lyrics::Lyrics;
+
+/// Lyrics of the playing track. Synced lyrics follow playback, plain ones
+/// are scrolled by hand.
+pub struct LyricsComponent {
+    lyrics: Option<Lyrics>,
+    current: Option<usize>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    Close,
+}
+
+impl LyricsComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            lyrics: None,
+            current: None,
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn set_lyrics(&mut self, lyrics: Option<Lyrics>) {
+        self.lyrics = lyrics;
+        self.current = None;
+        self.scroll = VerticalScroll::new();
+    }
+
+    /// Moves highlight to the line sung at `pos`.
+    pub fn set_position(&mut self, pos: Duration) {
+        let Some(lyrics) = self.lyrics.as_ref() else {
+            return;
+        };
+
+        let current = lyrics.current(pos);
+        if current == self.current {
+            return;
+        }
+
+        if let Some(line) = current {
+            self.scroll
+                .going_down
+                .set(self.current.is_none_or(|c| line > c));
+            self.scroll.pos.set(line);
+        }
+        self.current = current;
+    }
+
+    /// Highlighted line.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    fn len(&self) -> usize {
+        self.lyrics.as_ref().map_or(0, |l| l.lines.len())
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::LyricsComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for LyricsComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let area = {
+            let border = Block::bordered()
+                .title("Lyrics")
+                .border_style(self.theme.border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let Some(lyrics) = self.lyrics.as_ref() else {
+            Paragraph::new("No lyrics").render(area, buf);
+            return;
+        };
+
+        self.scroll.update_area(area, lyrics.lines.len());
+
+        let lines = lyrics
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll.y_offset.get())
+            .take(area.height as usize)
+            .map(|(i, line)| {
+                if Some(i) == self.current {
+                    Line::styled(line.text.as_str(), self.theme.heading)
+                } else if lyrics.is_synced() {
+                    Line::styled(line.text.as_str(), self.theme.dim)
+                } else {
+                    Line::raw(line.text.as_str())
+                }
+                .centered()
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(area, buf);
+    }
+}
+
+impl Component for LyricsComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.scroll_up {
+            self.scroll.move_up();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.scroll_down {
+            if self.len() > 0 {
+                self.scroll.move_down(self.len());
+            }
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+            Ok(EventState::Consumed)
+        } else {
+            Ok(EventState::NotConsumed)
+        }
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(self.key_config.scroll_up, "Scroll up"),
+            Binding::new(self.key_config.scroll_down, "Scroll down"),
+            Binding::new(self.key_config.close_popup, "Close"),
+        ]
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
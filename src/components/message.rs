@@ -0,0 +1,122 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Wrap};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+
+/// Shows an error or a report until dismissed.
+pub struct MessageComponent {
+    title: String,
+    text: String,
+    error: bool,
+    /// Lines scrolled past, for reports longer than the popup.
+    offset: usize,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    Close,
+}
+
+impl MessageComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            title: String::new(),
+            text: String::new(),
+            error: false,
+            offset: 0,
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn open(&mut self, title: String, text: String, error: bool) {
+        self.title = title;
+        self.text = text;
+        self.error = error;
+        self.offset = 0;
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::MessageComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for MessageComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let lines = self.text.lines().count() as u16;
+        let [area] = Layout::vertical([Constraint::Length(lines + 3)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let border_style = if self.error {
+            self.theme.error
+        } else {
+            self.theme.focused_border
+        };
+        let area = {
+            let border = Block::bordered()
+                .title(self.title.as_str())
+                .border_style(border_style);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let [text_area, hint_area] = Layout::new(
+            Direction::Vertical,
+            [Constraint::Fill(1), Constraint::Length(1)],
+        )
+        .areas(area);
+
+        Paragraph::new(self.text.as_str())
+            .wrap(Wrap { trim: false })
+            .scroll((self.offset as u16, 0))
+            .render(text_area, buf);
+        Line::styled(
+            format!("{}: close", self.key_config.confirm),
+            self.theme.dim,
+        )
+        .render(hint_area, buf);
+    }
+}
+
+impl Component for MessageComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.confirm || key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+        } else if key == self.key_config.scroll_down || key == Key::Down {
+            self.offset = (self.offset + 1).min(self.text.lines().count().saturating_sub(1));
+        } else if key == self.key_config.scroll_up || key == Key::Up {
+            self.offset = self.offset.saturating_sub(1);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::keys(&[self.key_config.scroll_up, Key::Up], "Scroll up"),
+            Binding::keys(&[self.key_config.scroll_down, Key::Down], "Scroll down"),
+            Binding::keys(
+                &[self.key_config.confirm, self.key_config.close_popup],
+                "Close",
+            ),
+        ]
+    }
+}
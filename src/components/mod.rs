@@ -1,13 +1,40 @@
+pub mod browser;
+pub mod command_line;
+pub mod command_palette;
+pub mod confirm;
+pub mod cover_art;
+pub mod device_picker;
+pub mod help;
+pub mod input;
+pub mod lyrics;
+pub mod message;
 pub mod player_controls;
 pub mod playlist;
+pub mod queue;
+pub mod stats;
+pub mod tag_editor;
 pub mod tracklist;
 pub mod utils;
 
+pub use browser::BrowserComponent;
+pub use command_line::CommandLineComponent;
+pub use command_palette::CommandPaletteComponent;
+pub use confirm::ConfirmComponent;
+pub use cover_art::CoverArtComponent;
+pub use device_picker::DevicePickerComponent;
+pub use help::{Binding, HelpComponent};
+pub use input::InputComponent;
+pub use lyrics::LyricsComponent;
+pub use message::MessageComponent;
 pub use player_controls::PlayerControlsComponent;
 pub use playlist::PlaylistComponent;
+pub use queue::QueueComponent;
+pub use stats::StatsComponent;
+pub use tag_editor::TagEditorComponent;
 pub use tracklist::TracklistComponent;
 
 use color_eyre::Result;
+use crossterm::event::MouseEvent;
 pub use ratatui::widgets::Widget;
 pub use ratatui::widgets::WidgetRef;
 
@@ -15,8 +42,50 @@ use crate::event::EventState;
 
 pub trait Component {
     fn event(&mut self, key: crate::event::Key) -> Result<EventState>;
+
+    fn mouse(&mut self, _mouse: MouseEvent) -> Result<EventState> {
+        Ok(EventState::NotConsumed)
+    }
+
+    /// Keys handled by [`Component::event`], listed in help.
+    fn bindings(&self) -> Vec<Binding> {
+        vec![]
+    }
 }
 
+#[allow(clippy::enum_variant_names)]
 pub enum ComponentCommand {
-    TracklistComponent(tracklist::Command)
+    TracklistComponent(tracklist::Command),
+    BrowserComponent(browser::Command),
+    DevicePickerComponent(device_picker::Command),
+    StatsComponent(stats::Command),
+    PlayerControlsComponent(player_controls::Command),
+    TagEditorComponent(tag_editor::Command),
+    LyricsComponent(lyrics::Command),
+    PlaylistComponent(playlist::Command),
+    QueueComponent(queue::Command),
+    CommandLineComponent(command_line::Command),
+    CommandPaletteComponent(command_palette::Command),
+    HelpComponent(help::Command),
+    ConfirmComponent(confirm::Command),
+    InputComponent(input::Command),
+    MessageComponent(message::Command),
+    /// Opens a generic popup on top of everything else.
+    OpenPopup(PopupRequest),
+}
+
+pub enum PopupRequest {
+    /// Sends `on_confirm` if the user agrees.
+    Confirm {
+        message: String,
+        on_confirm: Box<ComponentCommand>,
+    },
+    /// Asks for text starting as `value`, `placeholder` hints what to
+    /// type while it's empty.
+    Input {
+        title: String,
+        value: String,
+        placeholder: String,
+        on_submit: input::OnSubmit,
+    },
 }
@@ -1,26 +1,91 @@
+use std::cell::Cell;
+use std::time::Duration;
+
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::text::Line;
+use ratatui::layout::{Constraint, Direction, Layout, Position, Rect};
+use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Widget, WidgetRef};
+use uuid::Uuid;
+
+use super::{Binding, Component, ComponentCommand};
+use crate::components::utils::stars;
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::models::{MAX_RATING, Track};
+use crate::queue::Repeat;
+use crate::utils::format_position;
 
 pub struct PlayerControlsComponent {
     pub name: Option<String>,
-    pub progress: u16,
+    pub error: Option<String>,
+    pub position: Duration,
+    pub duration: Duration,
+    pub paused: bool,
+    pub volume: f32,
+    pub shuffle: bool,
+    pub repeat: Repeat,
+    /// Where progress and volume bars were drawn, for dragging them with the
+    /// mouse.
+    bar_area: Cell<Rect>,
+    volume_area: Cell<Rect>,
+    /// Playing track and its rating.
+    track: Option<(Uuid, u8, bool)>,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    SetRating { uuid: Uuid, rating: u8 },
+    SetFavorite { uuid: Uuid, favorite: bool },
+    Seek { pos: Duration },
+    SetVolume { volume: f32 },
 }
 
+/// Width of the volume label, bar and percentage together.
+const VOLUME_WIDTH: u16 = 20;
+
+/// Left aligned blocks from one to eight eighths of a cell wide.
+const EIGHTHS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
 impl PlayerControlsComponent {
-    pub fn new() -> Self {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
         PlayerControlsComponent {
             name: None,
-            progress: 0,
+            error: None,
+            position: Duration::ZERO,
+            duration: Duration::ZERO,
+            paused: false,
+            volume: 0.0,
+            shuffle: false,
+            repeat: Repeat::default(),
+            bar_area: Cell::new(Rect::default()),
+            volume_area: Cell::new(Rect::default()),
+            track: None,
+            key_config,
+            theme,
+            app_cmd_tx,
         }
     }
+
+    pub fn set_track(&mut self, track: Option<&Track>) {
+        self.track = track.map(|t| (t.uuid, t.rating, t.favorite));
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::PlayerControlsComponent(cmd))?;
+        Ok(())
+    }
 }
 
 impl WidgetRef for PlayerControlsComponent {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         let area = {
-            let border = Block::bordered();
+            let border = Block::bordered().border_style(self.theme.border);
             let a = border.inner(area);
             border.render(area, buf);
             a
@@ -46,17 +111,204 @@ impl WidgetRef for PlayerControlsComponent {
         )
         .areas(progress_area);
 
-        Line::raw(self.name.as_ref().map(|s| s.as_str()).unwrap_or("No name"))
-            .centered()
-            .render(name_area, buf);
+        let [elapsed_area, bar_area, total_area] = Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Length(9),
+                Constraint::Fill(1),
+                Constraint::Length(18),
+            ],
+        )
+        .areas(progress_area);
+
+        let name = self.name.as_deref().unwrap_or("No name");
+        let name = match self.track {
+            Some((_, rating, favorite)) => format!(
+                "{}{} {}",
+                if favorite { "♥ " } else { "" },
+                name,
+                stars(rating)
+            ),
+            None => name.to_string(),
+        };
+        Line::raw(name).centered().render(name_area, buf);
+
+        Line::raw(format!("{} ", format_position(self.position)))
+            .right_aligned()
+            .render(elapsed_area, buf);
+        Line::raw(format!(
+            " {} (-{})",
+            format_position(self.duration),
+            format_position(self.duration.saturating_sub(self.position))
+        ))
+        .render(total_area, buf);
+
+        self.render_bar(bar_area, buf);
+        self.bar_area.set(bar_area);
+
+        let [control_area, volume_area] = Layout::new(
+            Direction::Horizontal,
+            [Constraint::Fill(1), Constraint::Length(VOLUME_WIDTH)],
+        )
+        .areas(control_area);
+
+        match self.error.as_ref() {
+            Some(error) => Line::styled(error, self.theme.error)
+                .centered()
+                .render(control_area, buf),
+            None => self.indicators().centered().render(control_area, buf),
+        }
+
+        self.render_volume(volume_area, buf);
+    }
+}
+
+impl PlayerControlsComponent {
+    /// Gauge filled in eighths of a cell.
+    fn render_bar(&self, area: Rect, buf: &mut Buffer) {
+        let ratio = if self.duration.is_zero() {
+            0.0
+        } else {
+            (self.position.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+        };
+        let eighths = (ratio * area.width as f64 * 8.0).round() as u32;
+
+        for i in 0..area.width {
+            let Some(cell) = buf.cell_mut((area.x + i, area.y)) else {
+                continue;
+            };
+
+            let filled = eighths.saturating_sub(i as u32 * 8).min(8);
+            let symbol = match filled {
+                0 => ' ',
+                n => EIGHTHS[n as usize - 1],
+            };
+            cell.set_char(symbol).set_style(self.theme.progress);
+        }
+    }
+
+    fn indicators(&self) -> Line<'static> {
+        let on = self.theme.accent;
+        let off = self.theme.dim;
+
+        let state = match (self.track.is_some(), self.paused) {
+            (false, _) => Span::styled("■ Stopped", off),
+            (true, true) => Span::raw("⏸ Paused"),
+            (true, false) => Span::styled("▶ Playing", on),
+        };
+
+        Line::from(vec![
+            state,
+            Span::raw("   "),
+            Span::styled("Shuffle", if self.shuffle { on } else { off }),
+            Span::raw("   "),
+            Span::styled(
+                format!("Repeat {}", self.repeat.name()),
+                if self.repeat == Repeat::Off { off } else { on },
+            ),
+        ])
+    }
+
+    /// `Vol` label, a bar and percentage, the bar is what's dragged.
+    fn render_volume(&self, area: Rect, buf: &mut Buffer) {
+        let [label_area, bar_area, percent_area] = Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Length(4),
+                Constraint::Fill(1),
+                Constraint::Length(5),
+            ],
+        )
+        .areas(area);
+
+        Line::raw("Vol ").render(label_area, buf);
+        Line::raw(format!("{:.0}%", self.volume * 100.0))
+            .right_aligned()
+            .render(percent_area, buf);
+
+        let filled = (self.volume * bar_area.width as f32).round() as u16;
+        for i in 0..bar_area.width {
+            if let Some(cell) = buf.cell_mut((bar_area.x + i, bar_area.y)) {
+                cell.set_char(if i < filled { '█' } else { ' ' })
+                    .set_style(self.theme.progress);
+            }
+        }
+        self.volume_area.set(bar_area);
+    }
+}
+
+impl Component for PlayerControlsComponent {
+    /// Rates the playing track, works whatever component is focused.
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        let Some((uuid, rating, favorite)) = self.track else {
+            return Ok(EventState::NotConsumed);
+        };
+
+        if key == self.key_config.playing_rating_up {
+            self.send_command(Command::SetRating {
+                uuid,
+                rating: (rating + 1).min(MAX_RATING),
+            })?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.playing_rating_down {
+            self.send_command(Command::SetRating {
+                uuid,
+                rating: rating.saturating_sub(1),
+            })?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.playing_toggle_favorite {
+            self.send_command(Command::SetFavorite {
+                uuid,
+                favorite: !favorite,
+            })?;
+            Ok(EventState::Consumed)
+        } else {
+            Ok(EventState::NotConsumed)
+        }
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        vec![
+            Binding::new(k.playing_rating_up, "Rate playing track higher"),
+            Binding::new(k.playing_rating_down, "Rate playing track lower"),
+            Binding::new(
+                k.playing_toggle_favorite,
+                "Toggle favorite of playing track",
+            ),
+        ]
+    }
+
+    /// Clicking or dragging on the progress bar seeks, on the volume bar sets
+    /// volume.
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        let pressed = matches!(
+            mouse.kind,
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left)
+        );
+        if !pressed {
+            return Ok(EventState::NotConsumed);
+        }
+
+        let position = Position::new(mouse.column, mouse.row);
+        let ratio = |area: Rect| {
+            (mouse.column - area.x) as f64 / area.width.saturating_sub(1).max(1) as f64
+        };
 
-        let done = progress_area.width * self.progress / 100;
-        // eprintln!("{}, {}", progress_area.width, self.progress);
-        for i in 0..progress_area.width {
-            buf.cell_mut((i + progress_area.x, progress_area.y))
-                .map(|c| c.set_char(if i < done { '#' } else { '-' }));
+        let bar_area = self.bar_area.get();
+        let volume_area = self.volume_area.get();
+        if bar_area.contains(position) && !self.duration.is_zero() {
+            self.send_command(Command::Seek {
+                pos: self.duration.mul_f64(ratio(bar_area).min(1.0)),
+            })?;
+        } else if volume_area.contains(position) {
+            self.send_command(Command::SetVolume {
+                volume: ratio(volume_area).min(1.0) as f32,
+            })?;
+        } else {
+            return Ok(EventState::NotConsumed);
         }
 
-        Line::raw("Controls").centered().render(control_area, buf);
+        Ok(EventState::Consumed)
     }
 }
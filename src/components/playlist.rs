@@ -1 +1,229 @@
-pub struct PlaylistComponent {}
+use std::path::PathBuf;
+
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph};
+use uuid::Uuid;
+
+use super::{Binding, Component, ComponentCommand, PopupRequest, Widget, WidgetRef};
+use crate::components::utils::{ListMouse, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::models::Playlist;
+
+/// Playlist popup. Either picks a playlist to add tracks to or browses
+/// playlists to play and delete them. Last row asks for a name and creates
+/// a new playlist.
+pub struct PlaylistComponent {
+    /// Names and track counts.
+    playlists: Vec<(String, usize)>,
+    /// Tracks being added, `None` when browsing.
+    adding: Option<Vec<Uuid>>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    AddToPlaylist { name: String, tracks: Vec<Uuid> },
+    CreatePlaylist { name: String, tracks: Vec<Uuid> },
+    PlayPlaylist { name: String },
+    DeletePlaylist { name: String },
+    ExportPlaylist { name: String, path: PathBuf },
+    Close,
+}
+
+impl PlaylistComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            playlists: vec![],
+            adding: None,
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn set_playlists(&mut self, playlists: &[Playlist]) {
+        self.playlists = playlists
+            .iter()
+            .map(|p| (p.name.clone(), p.tracks.len()))
+            .collect();
+        if self.scroll.pos() > self.playlists.len() {
+            self.scroll.pos.set(self.playlists.len());
+        }
+    }
+
+    /// Opens popup for adding `tracks`, or for browsing if `None`.
+    pub fn open(&mut self, tracks: Option<Vec<Uuid>>) {
+        self.adding = tracks;
+        self.scroll = VerticalScroll::new();
+    }
+
+    /// Rows are playlists followed by the new playlist row.
+    fn len(&self) -> usize {
+        self.playlists.len() + 1
+    }
+
+    fn on_new_row(&self) -> bool {
+        self.scroll.pos() == self.playlists.len()
+    }
+
+    fn pick(&mut self) -> Result<()> {
+        if self.on_new_row() {
+            return self.create();
+        }
+
+        let Some((name, _)) = self.playlists.get(self.scroll.pos()) else {
+            return Ok(());
+        };
+        let name = name.clone();
+        match self.adding.clone() {
+            Some(tracks) => self.send_command(Command::AddToPlaylist { name, tracks })?,
+            None => self.send_command(Command::PlayPlaylist { name })?,
+        }
+
+        Ok(())
+    }
+
+    /// Asks for a name, the new playlist gets the tracks being added.
+    fn create(&self) -> Result<()> {
+        let tracks = self.adding.clone().unwrap_or_default();
+        self.app_cmd_tx
+            .send(ComponentCommand::OpenPopup(PopupRequest::Input {
+                title: "New playlist".to_string(),
+                value: String::new(),
+                placeholder: "Playlist name".to_string(),
+                on_submit: Box::new(|name| {
+                    ComponentCommand::PlaylistComponent(Command::CreatePlaylist { name, tracks })
+                }),
+            }))?;
+        Ok(())
+    }
+
+    fn delete(&self) -> Result<()> {
+        let Some((name, _)) = self.playlists.get(self.scroll.pos()) else {
+            return Ok(());
+        };
+
+        self.app_cmd_tx
+            .send(ComponentCommand::OpenPopup(PopupRequest::Confirm {
+                message: format!("Delete playlist \"{name}\"?"),
+                on_confirm: Box::new(ComponentCommand::PlaylistComponent(
+                    Command::DeletePlaylist { name: name.clone() },
+                )),
+            }))?;
+        Ok(())
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::PlaylistComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for PlaylistComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let title = match self.adding.as_ref() {
+                Some(tracks) if tracks.len() == 1 => "Add track to playlist".to_string(),
+                Some(tracks) => format!("Add {} tracks to playlist", tracks.len()),
+                None => "Playlists".to_string(),
+            };
+            let border = Block::bordered()
+                .title(title)
+                .border_style(self.theme.focused_border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        self.scroll.update_area(area, self.len());
+
+        let new_row = Line::styled("+ New playlist", self.theme.dim);
+        let lines = self
+            .playlists
+            .iter()
+            .map(|(name, count)| Line::raw(format!("  {name} ({count})")))
+            .chain(std::iter::once(new_row))
+            .skip(self.scroll.y_offset.get())
+            .take(area.height as usize)
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(area, buf);
+
+        let selection = self.scroll.pos() - self.scroll.y_offset.get();
+        for i in area.x..area.x + area.width {
+            if let Some(c) = buf.cell_mut((i, selection as u16 + area.y)) {
+                c.set_style(self.theme.selection);
+            }
+        }
+    }
+}
+
+impl Component for PlaylistComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+        } else if key == self.key_config.pick_playlist {
+            self.pick()?;
+        } else if key == self.key_config.create_playlist {
+            self.create()?;
+        } else if key == Key::Up || key == self.key_config.scroll_up {
+            self.scroll.move_up();
+        } else if key == Key::Down || key == self.key_config.scroll_down {
+            self.scroll.move_down(self.len());
+        } else if key == self.key_config.delete_playlist && self.adding.is_none() {
+            self.delete()?;
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        let mut bindings = vec![
+            Binding::keys(&[Key::Up, k.scroll_up], "Move up"),
+            Binding::keys(&[Key::Down, k.scroll_down], "Move down"),
+            Binding::new(
+                k.pick_playlist,
+                "Pick playlist, or create one on the last row",
+            ),
+            Binding::new(k.create_playlist, "Create playlist"),
+        ];
+        if self.adding.is_none() {
+            bindings.push(Binding::new(k.delete_playlist, "Delete playlist"));
+        }
+        bindings.push(Binding::new(k.close_popup, "Close"));
+        bindings
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            ListMouse::DoubleClicked(_) => {
+                self.pick()?;
+                Ok(EventState::Consumed)
+            }
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
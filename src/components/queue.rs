@@ -0,0 +1,193 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::{ListMouse, VerticalScroll};
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+
+/// Tracks that play after the current one, manually queued ones first.
+/// Changes are sent to the app, which owns the queue and hands back the
+/// new order.
+pub struct QueueComponent {
+    entries: Vec<QueueEntry>,
+    scroll: VerticalScroll,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub struct QueueEntry {
+    pub name: String,
+    /// Queued by hand rather than coming from the context.
+    pub manual: bool,
+}
+
+/// Indices are rows of the view, i.e. positions among upcoming tracks.
+pub enum Command {
+    MoveUp { index: usize },
+    MoveDown { index: usize },
+    Remove { index: usize },
+    Clear,
+    ShuffleRemaining,
+    PlayFrom { index: usize },
+    Close,
+}
+
+impl QueueComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            entries: vec![],
+            scroll: VerticalScroll::new(),
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn set_entries(&mut self, entries: Vec<QueueEntry>) {
+        self.entries = entries;
+        let last = self.entries.len().saturating_sub(1);
+        if self.scroll.pos() > last {
+            self.scroll.pos.set(last);
+        }
+    }
+
+    fn selected(&self) -> Option<usize> {
+        let pos = self.scroll.pos();
+        (pos < self.entries.len()).then_some(pos)
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::QueueComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for QueueComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let area = {
+            let manual = self.entries.iter().filter(|e| e.manual).count();
+            let title = format!(
+                "Up next ({manual} queued, {} from context)",
+                self.entries.len() - manual
+            );
+            let border = Block::bordered()
+                .title(title)
+                .border_style(self.theme.border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        if self.entries.is_empty() {
+            Paragraph::new("Nothing up next").render(area, buf);
+            return;
+        }
+
+        self.scroll.update_area(area, self.entries.len());
+
+        let lines = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(self.scroll.y_offset.get())
+            .take(area.height as usize)
+            .map(|(i, entry)| {
+                let number = Span::styled(format!("{:>3} ", i + 1), self.theme.dim);
+                if entry.manual {
+                    Line::from(vec![
+                        number,
+                        Span::styled(entry.name.as_str(), self.theme.accent),
+                    ])
+                } else {
+                    Line::from(vec![number, Span::raw(entry.name.as_str())])
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(area, buf);
+
+        let selection = self.scroll.pos() - self.scroll.y_offset.get();
+        for i in area.x..area.x + area.width {
+            if let Some(c) = buf.cell_mut((i, selection as u16 + area.y)) {
+                c.set_style(self.theme.selection);
+            }
+        }
+    }
+}
+
+impl Component for QueueComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        let len = self.entries.len();
+        let k = &self.key_config;
+
+        if key == Key::Up || key == k.scroll_up {
+            self.scroll.move_up();
+        } else if key == Key::Down || key == k.scroll_down {
+            if len > 0 {
+                self.scroll.move_down(len);
+            }
+        } else if key == k.queue_move_up {
+            if let Some(index) = self.selected().filter(|&i| i > 0) {
+                self.send_command(Command::MoveUp { index })?;
+                self.scroll.move_up();
+            }
+        } else if key == k.queue_move_down {
+            if let Some(index) = self.selected().filter(|&i| i + 1 < len) {
+                self.send_command(Command::MoveDown { index })?;
+                self.scroll.move_down(len);
+            }
+        } else if key == k.remove_from_queue {
+            if let Some(index) = self.selected() {
+                self.send_command(Command::Remove { index })?;
+            }
+        } else if key == k.clear_queue {
+            self.send_command(Command::Clear)?;
+        } else if key == k.shuffle_queue {
+            self.send_command(Command::ShuffleRemaining)?;
+        } else if key == k.play_audio {
+            if let Some(index) = self.selected() {
+                self.send_command(Command::PlayFrom { index })?;
+            }
+        } else if key == k.close_popup {
+            self.send_command(Command::Close)?;
+        } else {
+            return Ok(EventState::NotConsumed);
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        vec![
+            Binding::keys(&[Key::Up, k.scroll_up], "Move up"),
+            Binding::keys(&[Key::Down, k.scroll_down], "Move down"),
+            Binding::new(k.play_audio, "Play from here, skipping tracks before"),
+            Binding::new(k.queue_move_up, "Move track earlier"),
+            Binding::new(k.queue_move_down, "Move track later"),
+            Binding::new(k.remove_from_queue, "Remove track"),
+            Binding::new(k.clear_queue, "Clear everything up next"),
+            Binding::new(k.shuffle_queue, "Shuffle everything up next"),
+            Binding::new(k.close_popup, "Close"),
+        ]
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.entries.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            ListMouse::DoubleClicked(index) => {
+                self.send_command(Command::PlayFrom { index })?;
+                Ok(EventState::Consumed)
+            }
+            _ => Ok(EventState::Consumed),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use uuid::Uuid;
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::PLAYING_ICON;
+use crate::config::{KeyConfig, Theme};
+use crate::event::EventState;
+use crate::stats::{self, ListeningStats, Period, TopEntry};
+
+/// Days of the week shown as heatmap rows.
+const WEEK: u64 = 7;
+
+pub struct StatsComponent {
+    period: Period,
+    stats: ListeningStats,
+    playing: Option<Uuid>,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    SetPeriod { period: Period },
+    Close,
+}
+
+impl StatsComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            period: Period::default(),
+            stats: ListeningStats::default(),
+            playing: None,
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn period(&self) -> Period {
+        self.period
+    }
+
+    pub fn set_stats(&mut self, period: Period, stats: ListeningStats) {
+        self.period = period;
+        self.stats = stats;
+    }
+
+    pub fn set_playing(&mut self, playing: Option<Uuid>) {
+        self.playing = playing;
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::StatsComponent(cmd))?;
+        Ok(())
+    }
+
+    /// Playing track is marked in lists of tracks.
+    fn render_top(&self, title: &str, entries: &[TopEntry], area: Rect, buf: &mut Buffer) {
+        let area = {
+            let border = Block::bordered()
+                .title(title.to_string())
+                .border_style(self.theme.border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let lines = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let text = format!("{:>2}. {} ({})", i + 1, entry.name, entry.count);
+                if entry.track.is_some() && entry.track == self.playing {
+                    Line::styled(format!("{text} {PLAYING_ICON}"), self.theme.playing)
+                } else {
+                    Line::raw(text)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(lines).render(area, buf);
+    }
+
+    /// Grid of weeks (columns) by weekdays (rows) ending today, each cell
+    /// shaded by listening time relative to the busiest day.
+    fn render_heatmap(&self, area: Rect, buf: &mut Buffer) {
+        let area = {
+            let border = Block::bordered()
+                .title("Listening per day")
+                .border_style(self.theme.border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let weeks = (area.width / 2) as u64;
+        if weeks == 0 || area.height < WEEK as u16 {
+            return;
+        }
+
+        let today = stats::day(std::time::SystemTime::now());
+        // 1970-01-01 was a Thursday, rows start on Monday.
+        let weekday = |day: u64| (day + 3) % WEEK;
+        let first = today - weekday(today) - (weeks - 1) * WEEK;
+
+        let max = self
+            .stats
+            .per_day
+            .values()
+            .max()
+            .copied()
+            .unwrap_or_default();
+
+        for week in 0..weeks {
+            for row in 0..WEEK {
+                let day = first + week * WEEK + row;
+                if day > today {
+                    break;
+                }
+
+                let listened = self.stats.per_day.get(&day).copied().unwrap_or_default();
+                let level = if max.is_zero() {
+                    0.0
+                } else {
+                    listened.as_secs_f32() / max.as_secs_f32()
+                };
+
+                let level = match level {
+                    l if l <= 0.0 => 0,
+                    l if l < 0.25 => 1,
+                    l if l < 0.5 => 2,
+                    l if l < 0.75 => 3,
+                    _ => 4,
+                };
+                // Shades tell levels apart when there are no colors.
+                let symbol = if self.theme.has_colors() {
+                    '■'
+                } else {
+                    ['·', '░', '▒', '▓', '█'][level]
+                };
+
+                let x = area.x + week as u16 * 2;
+                let y = area.y + row as u16;
+                if let Some(cell) = buf.cell_mut((x, y)) {
+                    cell.set_char(symbol).set_fg(self.theme.heatmap[level]);
+                }
+            }
+        }
+    }
+}
+
+impl WidgetRef for StatsComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let area = {
+            let border = Block::bordered()
+                .title(format!("Stats for {}", self.period.name()))
+                .border_style(self.theme.border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let [summary_area, top_area, heatmap_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(WEEK as u16 + 2),
+        ])
+        .areas(area);
+
+        Line::from(vec![
+            Span::raw("Total listening time: "),
+            Span::styled(
+                crate::utils::format_duration(self.stats.total_listened),
+                self.theme.accent,
+            ),
+        ])
+        .render(summary_area, buf);
+
+        let [tracks_area, artists_area, albums_area, skipped_area] =
+            Layout::horizontal([Constraint::Fill(1); 4]).areas(top_area);
+
+        self.render_top("Top tracks", &self.stats.top_tracks, tracks_area, buf);
+        self.render_top("Top artists", &self.stats.top_artists, artists_area, buf);
+        self.render_top("Top albums", &self.stats.top_albums, albums_area, buf);
+        self.render_top("Most skipped", &self.stats.most_skipped, skipped_area, buf);
+
+        self.render_heatmap(heatmap_area, buf);
+    }
+}
+
+impl Component for StatsComponent {
+    fn event(&mut self, key: crate::event::Key) -> Result<EventState> {
+        if key == self.key_config.cycle_period {
+            self.send_command(Command::SetPeriod {
+                period: self.period.next(),
+            })?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+            Ok(EventState::Consumed)
+        } else {
+            Ok(EventState::NotConsumed)
+        }
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(self.key_config.cycle_period, "Cycle period"),
+            Binding::new(self.key_config.close_popup, "Close"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::config::ColorSupport;
+
+    fn heatmap(theme: Theme) -> String {
+        let (app_cmd_tx, _) = crossbeam_channel::unbounded();
+        let mut component = StatsComponent::new(KeyConfig::default(), theme, app_cmd_tx);
+
+        let today = stats::day(SystemTime::now());
+        let hour = Duration::from_secs(60 * 60);
+        let stats = ListeningStats {
+            per_day: HashMap::from([(today, 4 * hour), (today - 1, hour / 2)]),
+            ..Default::default()
+        };
+        component.set_stats(Period::default(), stats);
+
+        let area = Rect::new(0, 0, 10, WEEK as u16 + 2);
+        let mut buf = Buffer::empty(area);
+        component.render_heatmap(area, &mut buf);
+        buf.content.iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn heatmap_shades_without_colors() {
+        let cells = heatmap(Theme::dark().with_color_support(ColorSupport::None));
+        assert!(cells.contains('█'));
+        assert!(cells.contains('░'));
+        assert!(cells.contains('·'));
+        assert!(!cells.contains('■'));
+
+        let cells = heatmap(Theme::dark());
+        assert!(cells.contains('■'));
+        assert!(!cells.contains('█'));
+    }
+}
@@ -0,0 +1,458 @@
+use std::cell::Cell;
+
+use color_eyre::Result;
+use crossbeam_channel::Sender;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Position, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph};
+use uuid::Uuid;
+
+use super::{Binding, Component, ComponentCommand, Widget, WidgetRef};
+use crate::components::utils::TextInput;
+use crate::config::{KeyConfig, Theme};
+use crate::event::{EventState, Key};
+use crate::models::{Track, TrackTags};
+
+/// Edits tags of one track or of several at once. In batch edits fields
+/// whose values differ between tracks start as "keep existing".
+pub struct TagEditorComponent {
+    tracks: Vec<Track>,
+    /// `None` keeps each track's own value, empty string clears the tag.
+    values: [Option<String>; TagField::ALL.len()],
+    selected: usize,
+    /// One per field, each remembering values written into it before.
+    inputs: [TextInput; TagField::ALL.len()],
+    /// Where fields were last rendered, for selecting them with the mouse.
+    fields_area: Cell<Rect>,
+    error: Option<String>,
+    key_config: KeyConfig,
+    theme: Theme,
+    app_cmd_tx: Sender<ComponentCommand>,
+}
+
+pub enum Command {
+    /// New tags for every edited track.
+    Apply {
+        tags: Vec<(Uuid, TrackTags)>,
+    },
+    Close,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    TrackNumber,
+    Year,
+    Genre,
+}
+
+impl TagField {
+    const ALL: [TagField; 6] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::TrackNumber,
+        TagField::Year,
+        TagField::Genre,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            TagField::Title => "Title",
+            TagField::Artist => "Artist",
+            TagField::Album => "Album",
+            TagField::TrackNumber => "Track",
+            TagField::Year => "Year",
+            TagField::Genre => "Genre",
+        }
+    }
+
+    fn get(self, tags: &TrackTags) -> String {
+        match self {
+            TagField::Title => tags.title.clone().unwrap_or_default(),
+            TagField::Artist => tags.artist.clone().unwrap_or_default(),
+            TagField::Album => tags.album.clone().unwrap_or_default(),
+            TagField::TrackNumber => tags.track_number.map(|n| n.to_string()).unwrap_or_default(),
+            TagField::Year => tags.year.map(|y| y.to_string()).unwrap_or_default(),
+            TagField::Genre => tags.genre.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Fails with a message for the user when value doesn't fit the field.
+    fn set(self, tags: &mut TrackTags, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let text = (!value.is_empty()).then(|| value.to_string());
+
+        match self {
+            TagField::Title => tags.title = text,
+            TagField::Artist => tags.artist = text,
+            TagField::Album => tags.album = text,
+            TagField::Genre => tags.genre = text,
+            TagField::TrackNumber => {
+                tags.track_number = match text {
+                    Some(n) => Some(
+                        n.parse()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or(format!("Track number \"{n}\" is not a positive number"))?,
+                    ),
+                    None => None,
+                }
+            }
+            TagField::Year => {
+                tags.year = match text {
+                    Some(y) => Some(
+                        y.parse()
+                            .ok()
+                            .filter(|&y| y <= 9999)
+                            .ok_or(format!("Year \"{y}\" is not a valid year"))?,
+                    ),
+                    None => None,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TagEditorComponent {
+    pub fn new(key_config: KeyConfig, theme: Theme, app_cmd_tx: Sender<ComponentCommand>) -> Self {
+        Self {
+            tracks: vec![],
+            values: Default::default(),
+            selected: 0,
+            inputs: std::array::from_fn(|_| {
+                TextInput::new(&theme).with_placeholder("<keep existing>")
+            }),
+            fields_area: Cell::new(Rect::default()),
+            error: None,
+            key_config,
+            theme,
+            app_cmd_tx,
+        }
+    }
+
+    pub fn set_tracks(&mut self, tracks: Vec<Track>) {
+        for (value, field) in self.values.iter_mut().zip(TagField::ALL) {
+            let mut values = tracks.iter().map(|t| field.get(&t.tags));
+            let first = values.next();
+            *value = match first {
+                Some(first) if values.all(|v| v == first) => Some(first),
+                _ => None,
+            };
+        }
+
+        for (input, value) in self.inputs.iter_mut().zip(&self.values) {
+            input.set_value(value.clone().unwrap_or_default());
+        }
+
+        self.tracks = tracks;
+        self.selected = 0;
+        self.error = None;
+    }
+
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    /// Tags every track would get, or the first validation error.
+    fn result(&self) -> Result<Vec<(Uuid, TrackTags)>, String> {
+        self.tracks
+            .iter()
+            .map(|track| {
+                let mut tags = track.tags.clone();
+                for (value, field) in self.values.iter().zip(TagField::ALL) {
+                    if let Some(value) = value {
+                        field.set(&mut tags, value)?;
+                    }
+                }
+                Ok((track.uuid, tags))
+            })
+            .collect()
+    }
+
+    fn apply(&mut self) -> Result<()> {
+        match self.result() {
+            Ok(tags) => {
+                for (input, value) in self.inputs.iter_mut().zip(&self.values) {
+                    if let Some(value) = value {
+                        input.push_history(value.clone());
+                    }
+                }
+                self.send_command(Command::Apply { tags })?
+            }
+            Err(e) => self.error = Some(e),
+        }
+        Ok(())
+    }
+
+    /// Field turns from "keep existing" into a value only once text
+    /// actually changes, moving the cursor keeps it as is.
+    fn edit(&mut self, key: Key) -> EventState {
+        let input = &mut self.inputs[self.selected];
+        let before = input.value().to_string();
+        let state = input.event(key);
+        if input.value() != before {
+            self.values[self.selected] = Some(input.value().to_string());
+            self.error = None;
+        }
+        state
+    }
+
+    fn send_command(&self, cmd: Command) -> Result<()> {
+        self.app_cmd_tx
+            .send(ComponentCommand::TagEditorComponent(cmd))?;
+        Ok(())
+    }
+}
+
+impl WidgetRef for TagEditorComponent {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::vertical([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+
+        let area = {
+            let title = match self.tracks.len() {
+                1 => "Edit tags".to_string(),
+                n => format!("Edit tags of {n} tracks"),
+            };
+            let border = Block::bordered()
+                .title(title)
+                .border_style(self.theme.focused_border);
+            let a = border.inner(area);
+            border.render(area, buf);
+            a
+        };
+
+        let [fields_area, error_area, preview_area] = Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Length(TagField::ALL.len() as u16),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .areas(area);
+
+        let fields = self
+            .values
+            .iter()
+            .zip(TagField::ALL)
+            .enumerate()
+            .map(|(i, (value, field))| {
+                // Selected value is drawn by the input.
+                let value = match value {
+                    _ if i == self.selected => "",
+                    Some(value) => value,
+                    None => "<keep existing>",
+                };
+                format!("{:>7}: {value}", field.name())
+            })
+            .collect::<Vec<String>>();
+        Paragraph::new(fields.join("\n")).render(fields_area, buf);
+        self.fields_area.set(fields_area);
+
+        let selected_area = Rect {
+            y: fields_area.y + self.selected as u16,
+            height: 1,
+            ..fields_area
+        };
+        let [_, input_area] =
+            Layout::horizontal([Constraint::Length(9), Constraint::Fill(1)]).areas(selected_area);
+        self.inputs[self.selected].render_ref(input_area, buf);
+
+        for i in selected_area.x..selected_area.x + selected_area.width {
+            if let Some(c) = buf.cell_mut((i, selected_area.y)) {
+                c.set_style(self.theme.selection);
+            }
+        }
+
+        if let Some(error) = self.error.as_ref() {
+            Line::styled(error, self.theme.error).render(error_area, buf);
+        }
+
+        let preview_area = {
+            let border = Block::bordered()
+                .title("Preview")
+                .border_style(self.theme.border);
+            let a = border.inner(preview_area);
+            border.render(preview_area, buf);
+            a
+        };
+
+        let preview = match self.result() {
+            Ok(tags) => tags
+                .iter()
+                .zip(self.tracks.iter())
+                .take(preview_area.height as usize)
+                .map(|((_, new), track)| {
+                    let changed = if *new == track.tags { " " } else { "*" };
+                    format!(
+                        "{changed} {}. {} - {} ({}, {}) {}",
+                        TagField::TrackNumber.get(new),
+                        TagField::Artist.get(new),
+                        TagField::Title.get(new),
+                        TagField::Album.get(new),
+                        TagField::Year.get(new),
+                        TagField::Genre.get(new),
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Err(_) => String::new(),
+        };
+        Paragraph::new(preview).render(preview_area, buf);
+    }
+}
+
+impl Component for TagEditorComponent {
+    fn event(&mut self, key: Key) -> Result<EventState> {
+        if key == self.key_config.close_popup {
+            self.send_command(Command::Close)?;
+        } else if key == self.key_config.confirm {
+            self.apply()?;
+        } else if key == self.key_config.next_field {
+            self.selected = (self.selected + 1) % TagField::ALL.len();
+        } else if key == self.key_config.prev_field {
+            self.selected = (self.selected + TagField::ALL.len() - 1) % TagField::ALL.len();
+        } else if key == self.key_config.keep_existing {
+            self.values[self.selected] = None;
+            self.inputs[self.selected].clear();
+        } else {
+            return Ok(self.edit(key));
+        }
+
+        Ok(EventState::Consumed)
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        let mut bindings = vec![
+            Binding::new(k.next_field, "Next field"),
+            Binding::new(k.prev_field, "Previous field"),
+            Binding::new(k.keep_existing, "Keep each track's own value"),
+            Binding::new(k.confirm, "Write tags"),
+            Binding::new(k.close_popup, "Cancel"),
+        ];
+        // Editing keys taken by the editor itself are left out.
+        let taken = bindings
+            .iter()
+            .flat_map(|b| b.keys.clone())
+            .collect::<Vec<_>>();
+        let editing = TextInput::bindings()
+            .into_iter()
+            .filter(|b| !b.keys.iter().any(|k| taken.contains(k)));
+        bindings.extend(editing);
+        bindings
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        let area = self.fields_area.get();
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left)
+            || !area.contains(Position::new(mouse.column, mouse.row))
+        {
+            return Ok(EventState::NotConsumed);
+        }
+
+        self.selected = (mouse.row - area.y) as usize;
+        Ok(EventState::Consumed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crossbeam_channel::Receiver;
+
+    use super::*;
+    use crate::models::TrackStats;
+
+    fn editor(titles: &[&str]) -> (TagEditorComponent, Receiver<ComponentCommand>) {
+        let (app_cmd_tx, app_cmd_rx) = crossbeam_channel::unbounded();
+        let mut editor = TagEditorComponent::new(KeyConfig::default(), Theme::dark(), app_cmd_tx);
+        editor.set_tracks(titles.iter().map(|title| track(title)).collect());
+        (editor, app_cmd_rx)
+    }
+
+    fn track(title: &str) -> Track {
+        Track {
+            uuid: Uuid::new_v4(),
+            duration: Duration::ZERO,
+            path: PathBuf::from(format!("/music/{title}.mp3")),
+            tags: TrackTags {
+                title: Some(title.to_string()),
+                ..Default::default()
+            },
+            stats: TrackStats::default(),
+            rating: 0,
+            favorite: false,
+        }
+    }
+
+    fn press(editor: &mut TagEditorComponent, keys: &[Key]) {
+        for &key in keys {
+            editor.event(key).unwrap();
+        }
+    }
+
+    #[test]
+    fn editing_keys_reach_the_input() {
+        let (mut editor, _commands) = editor(&["Song"]);
+
+        press(&mut editor, &[Key::Ctrl('a'), Key::Right, Key::Ctrl('k')]);
+        assert_eq!(editor.values[0].as_deref(), Some("S"));
+        press(&mut editor, &[Key::Ctrl('y'), Key::Ctrl('w')]);
+        assert_eq!(editor.values[0].as_deref(), Some(""));
+
+        let keep_existing = editor.key_config.keep_existing;
+        press(&mut editor, &[keep_existing]);
+        assert_eq!(editor.values[0], None);
+    }
+
+    #[test]
+    fn fields_remember_written_values() {
+        let (mut editor, commands) = editor(&["A", "B"]);
+
+        press(&mut editor, &[Key::Tab]);
+        assert_eq!(editor.selected, 1);
+        press(
+            &mut editor,
+            &[
+                Key::Char('N'),
+                Key::Char('i'),
+                Key::Char('n'),
+                Key::Char('a'),
+                Key::Enter,
+            ],
+        );
+        let Ok(ComponentCommand::TagEditorComponent(Command::Apply { tags })) = commands.try_recv()
+        else {
+            panic!("Tags weren't applied");
+        };
+        assert!(
+            tags.iter()
+                .all(|(_, t)| t.artist.as_deref() == Some("Nina"))
+        );
+
+        editor.set_tracks(vec![track("C")]);
+        press(&mut editor, &[Key::Up]);
+        // Titles were kept as they were, nothing to walk back to.
+        assert_eq!(editor.values[0].as_deref(), Some("C"));
+        press(&mut editor, &[Key::BackTab, Key::Tab, Key::Tab, Key::Up]);
+        assert_eq!(editor.selected, 1);
+        assert_eq!(editor.values[1].as_deref(), Some("Nina"));
+    }
+}
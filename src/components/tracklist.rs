@@ -1,62 +1,537 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::str::FromStr;
 
 use color_eyre::Result;
 use crossbeam_channel::Sender;
+use crossterm::event::MouseEvent;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::widgets::{Block, Paragraph};
+use uuid::Uuid;
 
-use super::ComponentCommand;
-use super::{Component, Widget, WidgetRef};
-use crate::components::utils::VerticalScroll;
-use crate::config::KeyConfig;
+use super::{Binding, Component, Widget, WidgetRef};
+use super::{ComponentCommand, PopupRequest};
+use crate::components::utils::{ListMouse, PLAYING_ICON, VerticalScroll, stars};
+use crate::config::{KeyConfig, Theme};
 use crate::event::EventState;
-use crate::models::Track;
+use crate::models::{MAX_RATING, Track};
 
 pub struct TracklistComponent {
+    /// Whole library, `library` is what passes `filter`.
+    all: Vec<Track>,
     library: Vec<Track>,
+    sort: TrackSort,
+    filter: TrackFilter,
+    /// Only tracks of this artist are shown, picked in the browser. Tracks
+    /// without an artist are under the empty name.
+    artist: Option<String>,
     scroll: VerticalScroll,
+    /// Tracks marked for bulk operations.
+    marked: HashSet<Uuid>,
+    /// Where visual range selection started, tracks between it and the
+    /// cursor count as marked.
+    visual_anchor: Option<usize>,
+    playing: Option<Uuid>,
+    /// Cursor moves to the playing track whenever it changes.
+    follow: bool,
     key_config: KeyConfig,
+    theme: Theme,
     app_cmd_tx: Sender<ComponentCommand>,
 }
 
 pub enum Command {
-    SetCurrentTrack { path: PathBuf },
+    /// Play track at `index` and continue through `context` after it.
+    PlayTrack {
+        context: Vec<Uuid>,
+        index: usize,
+    },
+    AddToQueue {
+        uuid: Uuid,
+    },
+    AddToPlaylist {
+        uuids: Vec<Uuid>,
+    },
+    RemoveFromLibrary {
+        uuids: Vec<Uuid>,
+    },
+    SetRating {
+        uuid: Uuid,
+        rating: u8,
+    },
+    SetFavorite {
+        uuid: Uuid,
+        favorite: bool,
+    },
+    EditTags {
+        uuids: Vec<Uuid>,
+    },
+    UndoTagEdit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TrackSort {
+    #[default]
+    Path,
+    Title,
+    Artist,
+    Album,
+    PlayCount,
+    SkipCount,
+    LastPlayed,
+    Rating,
+}
+
+impl TrackSort {
+    pub const ALL: [TrackSort; 8] = [
+        TrackSort::Path,
+        TrackSort::Title,
+        TrackSort::Artist,
+        TrackSort::Album,
+        TrackSort::PlayCount,
+        TrackSort::SkipCount,
+        TrackSort::LastPlayed,
+        TrackSort::Rating,
+    ];
+
+    fn next(self) -> Self {
+        match self {
+            TrackSort::Path => TrackSort::Title,
+            TrackSort::Title => TrackSort::Artist,
+            TrackSort::Artist => TrackSort::Album,
+            TrackSort::Album => TrackSort::PlayCount,
+            TrackSort::PlayCount => TrackSort::SkipCount,
+            TrackSort::SkipCount => TrackSort::LastPlayed,
+            TrackSort::LastPlayed => TrackSort::Rating,
+            TrackSort::Rating => TrackSort::Path,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TrackSort::Path => "path",
+            TrackSort::Title => "title",
+            TrackSort::Artist => "artist",
+            TrackSort::Album => "album",
+            TrackSort::PlayCount => "plays",
+            TrackSort::SkipCount => "skips",
+            TrackSort::LastPlayed => "last played",
+            TrackSort::Rating => "rating",
+        }
+    }
+
+    /// Counters and dates sort descending, most played first. Tracks
+    /// missing the tag go last when sorting by it.
+    fn sort(self, tracks: &mut [Track]) {
+        match self {
+            TrackSort::Path => tracks.sort_by(|a, b| a.path.cmp(&b.path)),
+            TrackSort::Title => tracks.sort_by_cached_key(|t| t.name().to_lowercase()),
+            TrackSort::Artist => tracks.sort_by_cached_key(|t| {
+                (
+                    tag_key(&t.tags.artist),
+                    tag_key(&t.tags.album),
+                    t.tags.track_number,
+                    t.path.clone(),
+                )
+            }),
+            TrackSort::Album => tracks.sort_by_cached_key(|t| {
+                (tag_key(&t.tags.album), t.tags.track_number, t.path.clone())
+            }),
+            TrackSort::PlayCount => tracks.sort_by_key(|t| std::cmp::Reverse(t.stats.play_count)),
+            TrackSort::SkipCount => tracks.sort_by_key(|t| std::cmp::Reverse(t.stats.skip_count)),
+            TrackSort::LastPlayed => tracks.sort_by_key(|t| std::cmp::Reverse(t.stats.last_played)),
+            TrackSort::Rating => tracks.sort_by_key(|t| std::cmp::Reverse((t.rating, t.favorite))),
+        }
+    }
+}
+
+impl FromStr for TrackSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TrackSort::ALL
+            .into_iter()
+            .find(|sort| sort.name() == s.trim())
+            .ok_or_else(|| format!("Unknown sort \"{s}\""))
+    }
+}
+
+/// Sorts tracks without the tag after ones with it.
+fn tag_key(tag: &Option<String>) -> (bool, String) {
+    (
+        tag.is_none(),
+        tag.as_deref().unwrap_or_default().to_lowercase(),
+    )
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TrackFilter {
+    #[default]
+    All,
+    Favorites,
+    /// Tracks rated at least this many stars.
+    MinRating(u8),
+}
+
+impl TrackFilter {
+    fn next(self) -> Self {
+        match self {
+            TrackFilter::All => TrackFilter::Favorites,
+            TrackFilter::Favorites => TrackFilter::MinRating(1),
+            TrackFilter::MinRating(stars) if stars < MAX_RATING => {
+                TrackFilter::MinRating(stars + 1)
+            }
+            TrackFilter::MinRating(_) => TrackFilter::All,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            TrackFilter::All => "all".to_string(),
+            TrackFilter::Favorites => "favorites".to_string(),
+            TrackFilter::MinRating(stars) => format!("{stars}+ stars"),
+        }
+    }
+
+    fn matches(self, track: &Track) -> bool {
+        match self {
+            TrackFilter::All => true,
+            TrackFilter::Favorites => track.favorite,
+            TrackFilter::MinRating(stars) => track.rating >= stars,
+        }
+    }
+}
+
+/// Accepts names from [`TrackFilter::name`], stars can be given as just `3`
+/// or `3+`.
+impl FromStr for TrackFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s {
+            "all" => return Ok(TrackFilter::All),
+            "favorites" => return Ok(TrackFilter::Favorites),
+            _ => {}
+        }
+
+        let stars = s.trim_end_matches("stars").trim().trim_end_matches('+');
+        match stars.parse() {
+            Ok(stars) if (1..=MAX_RATING).contains(&stars) => Ok(TrackFilter::MinRating(stars)),
+            _ => Err(format!("Unknown filter \"{s}\"")),
+        }
+    }
 }
 
 impl TracklistComponent {
     pub fn new(
         lib: Vec<Track>,
         key_config: KeyConfig,
+        theme: Theme,
         app_cmd_tx: Sender<ComponentCommand>,
     ) -> Self {
-        Self {
-            library: lib,
+        let mut tracklist = Self {
+            all: lib,
+            library: vec![],
+            sort: TrackSort::default(),
+            filter: TrackFilter::default(),
+            artist: None,
             scroll: VerticalScroll::new(),
+            marked: HashSet::new(),
+            visual_anchor: None,
+            playing: None,
+            follow: false,
             key_config,
+            theme,
             app_cmd_tx,
+        };
+        tracklist.refresh();
+        tracklist
+    }
+
+    /// Replaces track with the same uuid, e.g. after its stats changed.
+    pub fn update_track(&mut self, track: &Track) {
+        if let Some(t) = self.all.iter_mut().find(|t| t.uuid == track.uuid) {
+            *t = track.clone();
+        }
+        if let Some(t) = self.library.iter_mut().find(|t| t.uuid == track.uuid) {
+            *t = track.clone();
+        }
+    }
+
+    pub fn remove_tracks(&mut self, uuids: &[Uuid]) {
+        self.all.retain(|t| !uuids.contains(&t.uuid));
+        for uuid in uuids {
+            self.marked.remove(uuid);
+        }
+        self.visual_anchor = None;
+        self.refresh();
+    }
+
+    pub fn set_playing(&mut self, playing: Option<Uuid>) {
+        self.playing = playing;
+        // Range selection would grow with every track played.
+        if self.follow && self.visual_anchor.is_none() {
+            self.jump_to_playing();
+        }
+    }
+
+    pub fn set_follow(&mut self, follow: bool) {
+        self.follow = follow;
+        if follow {
+            self.jump_to_playing();
+        }
+    }
+
+    /// Moves cursor to the playing track if it's shown.
+    fn jump_to_playing(&mut self) {
+        let index = self
+            .playing
+            .and_then(|uuid| self.library.iter().position(|t| t.uuid == uuid));
+        if let Some(index) = index {
+            self.scroll.jump(index, self.library.len());
+        }
+    }
+
+    /// Tracks as shown, filtered and sorted.
+    pub fn tracks(&self) -> &[Track] {
+        &self.library
+    }
+
+    pub fn set_sort(&mut self, sort: TrackSort) {
+        self.sort = sort;
+        self.refresh();
+    }
+
+    pub fn set_filter(&mut self, filter: TrackFilter) {
+        self.filter = filter;
+        self.refresh();
+    }
+
+    /// Also refilters by the current tags, which
+    /// [`TracklistComponent::update_track`] doesn't.
+    pub fn set_artist(&mut self, artist: Option<String>) {
+        self.artist = artist;
+        self.refresh();
+    }
+
+    /// Filters and sorts library keeping the selected track selected if it's
+    /// still shown.
+    fn refresh(&mut self) {
+        let selected = self.cursor().map(|t| t.uuid);
+        // Range is by index, it can't survive reordering.
+        self.end_visual();
+
+        self.library = self
+            .all
+            .iter()
+            .filter(|t| self.filter.matches(t))
+            .filter(|t| {
+                self.artist
+                    .as_ref()
+                    .is_none_or(|a| t.tags.artist.as_deref().unwrap_or_default() == a)
+            })
+            .cloned()
+            .collect();
+        self.sort.sort(&mut self.library);
+
+        match selected.and_then(|u| self.library.iter().position(|t| t.uuid == u)) {
+            Some(index) => self.scroll.pos.set(index),
+            None => self
+                .scroll
+                .pos
+                .set(self.scroll.pos().min(self.library.len().saturating_sub(1))),
+        }
+        // Offset is only adjusted one line at a time, list may have shrunk
+        // past it.
+        self.scroll
+            .y_offset
+            .set(self.scroll.y_offset.get().min(self.scroll.pos()));
+    }
+
+    fn cursor(&self) -> Option<&Track> {
+        self.library.get(self.scroll.pos())
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        let in_range = self.visual_anchor.is_some_and(|anchor| {
+            let pos = self.scroll.pos();
+            (anchor.min(pos)..=anchor.max(pos)).contains(&index)
+        });
+        in_range || self.marked.contains(&self.library[index].uuid)
+    }
+
+    /// Tracks bulk operations apply to, marked ones in list order or the one
+    /// under the cursor if nothing is marked.
+    fn selection(&self) -> Vec<&Track> {
+        let marked = (0..self.library.len())
+            .filter(|&i| self.is_marked(i))
+            .map(|i| &self.library[i])
+            .collect::<Vec<_>>();
+
+        if marked.is_empty() {
+            self.cursor().into_iter().collect()
+        } else {
+            marked
+        }
+    }
+
+    /// Starts visual range selection or marks the range and ends it.
+    fn toggle_visual(&mut self) {
+        match self.visual_anchor {
+            Some(_) => self.end_visual(),
+            None if !self.library.is_empty() => self.visual_anchor = Some(self.scroll.pos()),
+            None => {}
         }
     }
 
+    fn end_visual(&mut self) {
+        if self.visual_anchor.is_none() {
+            return;
+        }
+        let range = (0..self.library.len())
+            .filter(|&i| self.is_marked(i))
+            .map(|i| self.library[i].uuid)
+            .collect::<Vec<_>>();
+        self.marked.extend(range);
+        self.visual_anchor = None;
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(uuid) = self.cursor().map(|t| t.uuid)
+            && !self.marked.remove(&uuid)
+        {
+            self.marked.insert(uuid);
+        }
+        self.next_col();
+    }
+
+    fn mark_all(&mut self) {
+        self.end_visual();
+        self.marked.extend(self.library.iter().map(|t| t.uuid));
+    }
+
+    /// Inverts marks of shown tracks.
+    fn invert_marks(&mut self) {
+        self.end_visual();
+        for track in self.library.iter() {
+            if !self.marked.remove(&track.uuid) {
+                self.marked.insert(track.uuid);
+            }
+        }
+    }
+
+    /// Returns whether there was anything to clear.
+    fn clear_marks(&mut self) -> bool {
+        let had_marks = !self.marked.is_empty() || self.visual_anchor.is_some();
+        self.marked.clear();
+        self.visual_anchor = None;
+        had_marks
+    }
+
     fn next_col(&self) {
-        self.scroll.move_down(self.library.len());
+        if !self.library.is_empty() {
+            self.scroll.move_down(self.library.len());
+        }
     }
 
     fn prev_col(&self) {
         self.scroll.move_up();
     }
 
-    fn play_selected(&mut self) -> Result<()> {
-        let index = self.scroll.pos();
-        let path = self.library.get(index).unwrap().path.as_path();
-        self.send_command(Command::SetCurrentTrack {
-            path: path.to_path_buf(),
+    pub fn play_selected(&mut self) -> Result<()> {
+        if self.library.is_empty() {
+            return Ok(());
+        }
+
+        self.send_command(Command::PlayTrack {
+            context: self.library.iter().map(|t| t.uuid).collect(),
+            index: self.scroll.pos(),
         })?;
 
         Ok(())
     }
 
+    pub fn selected_uuids(&self) -> Vec<Uuid> {
+        self.selection().iter().map(|t| t.uuid).collect()
+    }
+
+    fn queue_selected(&mut self) -> Result<()> {
+        for uuid in self.selected_uuids() {
+            self.send_command(Command::AddToQueue { uuid })?;
+        }
+        self.clear_marks();
+
+        Ok(())
+    }
+
+    fn rate_selected(&mut self, up: bool) -> Result<()> {
+        for track in self.selection() {
+            let rating = if up {
+                (track.rating + 1).min(MAX_RATING)
+            } else {
+                track.rating.saturating_sub(1)
+            };
+            self.send_command(Command::SetRating {
+                uuid: track.uuid,
+                rating,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn edit_selected(&mut self) -> Result<()> {
+        let uuids = self.selected_uuids();
+        if !uuids.is_empty() {
+            self.send_command(Command::EditTags { uuids })?;
+        }
+
+        Ok(())
+    }
+
+    fn add_selected_to_playlist(&mut self) -> Result<()> {
+        let uuids = self.selected_uuids();
+        if !uuids.is_empty() {
+            self.send_command(Command::AddToPlaylist { uuids })?;
+            self.clear_marks();
+        }
+
+        Ok(())
+    }
+
+    /// Asks first, removed tracks only come back by editing the database.
+    fn remove_selected(&mut self) -> Result<()> {
+        let uuids = self.selected_uuids();
+        let message = match uuids.len() {
+            0 => return Ok(()),
+            1 => "Remove track from library?".to_string(),
+            n => format!("Remove {n} tracks from library?"),
+        };
+
+        self.app_cmd_tx
+            .send(ComponentCommand::OpenPopup(PopupRequest::Confirm {
+                message,
+                on_confirm: Box::new(ComponentCommand::TracklistComponent(
+                    Command::RemoveFromLibrary { uuids },
+                )),
+            }))?;
+        Ok(())
+    }
+
+    /// Favorites all selected tracks, unless all of them already are.
+    fn toggle_favorite_selected(&mut self) -> Result<()> {
+        let selection = self.selection();
+        let favorite = !selection.iter().all(|t| t.favorite);
+        for track in selection {
+            self.send_command(Command::SetFavorite {
+                uuid: track.uuid,
+                favorite,
+            })?;
+        }
+
+        Ok(())
+    }
+
     fn send_command(&self, cmd: Command) -> Result<()> {
         self.app_cmd_tx
             .send(ComponentCommand::TracklistComponent(cmd))?;
@@ -67,13 +542,35 @@ impl TracklistComponent {
 impl WidgetRef for TracklistComponent {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         let area = {
-            let border = Block::bordered();
+            let tracks = match self.artist.as_deref() {
+                None => "Tracks".to_string(),
+                Some("") => "Tracks of unknown artists".to_string(),
+                Some(artist) => format!("Tracks of {artist}"),
+            };
+            let mut title = match self.filter {
+                TrackFilter::All => format!("{tracks} by {}", self.sort.name()),
+                filter => format!("{tracks} by {} ({})", self.sort.name(), filter.name()),
+            };
+            let marked = (0..self.library.len())
+                .filter(|&i| self.is_marked(i))
+                .count();
+            if self.visual_anchor.is_some() {
+                title.push_str(&format!(" -- VISUAL {marked} --"));
+            } else if marked > 0 {
+                title.push_str(&format!(" [{marked} selected]"));
+            }
+            if self.follow {
+                title.push_str(" [following playback]");
+            }
+            let border = Block::bordered()
+                .title(title)
+                .border_style(self.theme.border);
             let a = border.inner(area);
             border.render(area, buf);
             a
         };
 
-        self.scroll.update(area.height as usize, self.library.len());
+        self.scroll.update_area(area, self.library.len());
 
         let tracks = self
             .library
@@ -81,7 +578,8 @@ impl WidgetRef for TracklistComponent {
             .skip(self.scroll.y_offset.get())
             .take(area.height as usize)
             .map(|t| {
-                t.path
+                let name = t
+                    .path
                     .to_path_buf()
                     .file_name()
                     .unwrap()
@@ -89,17 +587,51 @@ impl WidgetRef for TracklistComponent {
                     .split('[')
                     .next()
                     .unwrap()
-                    .to_string()
+                    .to_string();
+                format!(
+                    "{}{} {} {}",
+                    if Some(t.uuid) == self.playing {
+                        PLAYING_ICON
+                    } else {
+                        ' '
+                    },
+                    if t.favorite { '♥' } else { ' ' },
+                    stars(t.rating),
+                    name
+                )
             })
             .collect::<Vec<String>>();
 
         Paragraph::new(tracks.join("\n")).render(area, buf);
 
+        let visible = self.scroll.y_offset.get()
+            ..(self.scroll.y_offset.get() + area.height as usize).min(self.library.len());
+        let playing = visible
+            .clone()
+            .find(|&i| Some(self.library[i].uuid) == self.playing);
+        if let Some(index) = playing {
+            let y = (index - self.scroll.y_offset.get()) as u16 + area.y;
+            for i in area.x..area.x + area.width {
+                if let Some(c) = buf.cell_mut((i, y)) {
+                    c.set_style(self.theme.playing);
+                }
+            }
+        }
+        for index in visible.filter(|&i| self.is_marked(i)) {
+            let y = (index - self.scroll.y_offset.get()) as u16 + area.y;
+            for i in area.x..area.x + area.width {
+                if let Some(c) = buf.cell_mut((i, y)) {
+                    c.set_style(self.theme.marked);
+                }
+            }
+        }
+
         if !self.library.is_empty() {
             let selection = self.scroll.pos() - self.scroll.y_offset.get();
-            for i in 0 + area.x..=area.width {
-                buf.cell_mut((i, selection as u16 + area.y))
-                    .map(|c| c.set_bg(Color::Blue));
+            for i in area.x..area.x + area.width {
+                if let Some(c) = buf.cell_mut((i, selection as u16 + area.y)) {
+                    c.set_style(self.theme.selection);
+                }
             }
         }
     }
@@ -116,8 +648,95 @@ impl Component for TracklistComponent {
         } else if key == self.key_config.play_audio {
             self.play_selected()?;
             Ok(EventState::Consumed)
+        } else if key == self.key_config.add_to_manual_queue {
+            self.queue_selected()?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.cycle_sort {
+            self.set_sort(self.sort.next());
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.cycle_filter {
+            self.set_filter(self.filter.next());
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.rating_up {
+            self.rate_selected(true)?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.rating_down {
+            self.rate_selected(false)?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.toggle_favorite {
+            self.toggle_favorite_selected()?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.edit_tags {
+            self.edit_selected()?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.undo_tag_edit {
+            self.send_command(Command::UndoTagEdit)?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.visual_select {
+            self.toggle_visual();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.toggle_select {
+            self.toggle_mark();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.select_all {
+            self.mark_all();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.invert_selection {
+            self.invert_marks();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.add_to_playlist {
+            self.add_selected_to_playlist()?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.remove_from_library {
+            self.remove_selected()?;
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.jump_to_playing {
+            self.jump_to_playing();
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.toggle_follow_playback {
+            self.set_follow(!self.follow);
+            Ok(EventState::Consumed)
+        } else if key == self.key_config.clear_selection && self.clear_marks() {
+            Ok(EventState::Consumed)
         } else {
             Ok(EventState::NotConsumed)
         }
     }
+
+    fn bindings(&self) -> Vec<Binding> {
+        let k = &self.key_config;
+        vec![
+            Binding::new(k.scroll_up, "Move up"),
+            Binding::new(k.scroll_down, "Move down"),
+            Binding::new(k.play_audio, "Play track"),
+            Binding::new(k.add_to_manual_queue, "Add selected tracks to queue"),
+            Binding::new(k.cycle_sort, "Cycle sort order"),
+            Binding::new(k.cycle_filter, "Cycle filter"),
+            Binding::new(k.rating_up, "Rate selected tracks higher"),
+            Binding::new(k.rating_down, "Rate selected tracks lower"),
+            Binding::new(k.toggle_favorite, "Toggle favorite of selected tracks"),
+            Binding::new(k.edit_tags, "Edit tags of selected tracks"),
+            Binding::new(k.undo_tag_edit, "Undo last tag edit"),
+            Binding::new(k.visual_select, "Start or end range selection"),
+            Binding::new(k.toggle_select, "Select track under cursor"),
+            Binding::new(k.select_all, "Select all tracks"),
+            Binding::new(k.invert_selection, "Invert selection"),
+            Binding::new(k.clear_selection, "Clear selection"),
+            Binding::new(k.add_to_playlist, "Add selected tracks to playlist"),
+            Binding::new(k.remove_from_library, "Remove selected tracks from library"),
+            Binding::new(k.jump_to_playing, "Jump to playing track"),
+            Binding::new(k.toggle_follow_playback, "Toggle following playback"),
+        ]
+    }
+
+    fn mouse(&mut self, mouse: MouseEvent) -> Result<EventState> {
+        match self.scroll.mouse(mouse, self.library.len()) {
+            ListMouse::Missed => Ok(EventState::NotConsumed),
+            ListMouse::DoubleClicked(_) => {
+                self.play_selected()?;
+                Ok(EventState::Consumed)
+            }
+            _ => Ok(EventState::Consumed),
+        }
+    }
 }
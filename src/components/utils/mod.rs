@@ -1,3 +1,16 @@
+mod text_input;
 mod vertical_scroll;
 
-pub use vertical_scroll::VerticalScroll;
+pub use text_input::TextInput;
+pub use vertical_scroll::{ListMouse, VerticalScroll};
+
+use crate::models::MAX_RATING;
+
+/// Marks the playing track in lists.
+pub const PLAYING_ICON: char = '▶';
+
+/// Rating as filled and empty stars, e.g. `★★★☆☆`.
+pub fn stars(rating: u8) -> String {
+    let rating = rating.min(MAX_RATING) as usize;
+    "★".repeat(rating) + &"☆".repeat(MAX_RATING as usize - rating)
+}
@@ -0,0 +1,376 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Widget, WidgetRef};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::components::Binding;
+use crate::config::Theme;
+use crate::event::{EventState, Key};
+
+/// Entries kept in history, older ones are dropped.
+const MAX_HISTORY: usize = 100;
+
+/// Single line text input with readline style editing. Cursor moves by
+/// grapheme so combined characters and emoji are edited as one.
+pub struct TextInput {
+    value: String,
+    /// Byte offset into `value`, always on a grapheme boundary.
+    cursor: usize,
+    /// Shown dimmed while the input is empty.
+    placeholder: String,
+    placeholder_style: Style,
+    /// Text removed by the last kill, inserted back by yank.
+    killed: String,
+    /// Oldest first.
+    history: Vec<String>,
+    /// Entry shown while walking history, `None` when editing a new line.
+    history_pos: Option<usize>,
+    /// Line being typed before walking history.
+    draft: String,
+}
+
+impl TextInput {
+    pub fn new(theme: &Theme) -> Self {
+        TextInput {
+            value: String::new(),
+            cursor: 0,
+            placeholder: String::new(),
+            placeholder_style: theme.dim,
+            killed: String::new(),
+            history: vec![],
+            history_pos: None,
+            draft: String::new(),
+        }
+    }
+
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces text, cursor goes to the end.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+        self.history_pos = None;
+    }
+
+    pub fn set_placeholder(&mut self, placeholder: impl Into<String>) {
+        self.placeholder = placeholder.into();
+    }
+
+    pub fn clear(&mut self) {
+        self.set_value(String::new());
+    }
+
+    /// Remembers submitted text for walking back to it with Up.
+    pub fn push_history(&mut self, entry: String) {
+        if entry.is_empty() {
+            return;
+        }
+        self.history.retain(|e| *e != entry);
+        self.history.push(entry);
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history_pos = None;
+    }
+
+    /// Handles editing keys, anything else is left to the caller. Up and
+    /// Down are only consumed when there's history.
+    pub fn event(&mut self, key: Key) -> EventState {
+        match key {
+            Key::Char(c) => self.insert(&c.to_string()),
+            Key::Left | Key::Ctrl('b') => self.cursor = self.prev_boundary(),
+            Key::Right | Key::Ctrl('f') => self.cursor = self.next_boundary(),
+            Key::Alt('b') => self.cursor = self.word_start(),
+            Key::Alt('f') => self.cursor = self.word_end(),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.value.len(),
+            Key::Backspace | Key::Ctrl('h') => {
+                let start = self.prev_boundary();
+                self.value.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            Key::Delete | Key::Ctrl('d') => {
+                self.value
+                    .replace_range(self.cursor..self.next_boundary(), "");
+            }
+            Key::Ctrl('w') => self.kill(self.word_start(), self.cursor),
+            Key::Alt('d') => self.kill(self.cursor, self.word_end()),
+            Key::Ctrl('u') => self.kill(0, self.cursor),
+            Key::Ctrl('k') => self.kill(self.cursor, self.value.len()),
+            Key::Ctrl('y') => self.insert(&self.killed.clone()),
+            Key::Up if !self.history.is_empty() => self.history_prev(),
+            Key::Down if !self.history.is_empty() => self.history_next(),
+            _ => return EventState::NotConsumed,
+        }
+
+        EventState::Consumed
+    }
+
+    /// Keys handled by [`TextInput::event`], for help.
+    pub fn bindings() -> Vec<Binding> {
+        vec![
+            Binding::keys(&[Key::Left, Key::Ctrl('b')], "Cursor left"),
+            Binding::keys(&[Key::Right, Key::Ctrl('f')], "Cursor right"),
+            Binding::new(Key::Alt('b'), "Word left"),
+            Binding::new(Key::Alt('f'), "Word right"),
+            Binding::keys(&[Key::Home, Key::Ctrl('a')], "Start of line"),
+            Binding::keys(&[Key::End, Key::Ctrl('e')], "End of line"),
+            Binding::keys(&[Key::Delete, Key::Ctrl('d')], "Delete character"),
+            Binding::new(Key::Ctrl('w'), "Delete word before cursor"),
+            Binding::new(Key::Alt('d'), "Delete word after cursor"),
+            Binding::new(Key::Ctrl('u'), "Delete to start of line"),
+            Binding::new(Key::Ctrl('k'), "Delete to end of line"),
+            Binding::new(Key::Ctrl('y'), "Paste deleted text"),
+            Binding::keys(&[Key::Up, Key::Down], "Walk history"),
+        ]
+    }
+
+    fn insert(&mut self, s: &str) {
+        self.value.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    fn kill(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        self.killed = self.value[from..to].to_string();
+        self.value.replace_range(from..to, "");
+        self.cursor = from;
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.prev_boundary_of(self.cursor)
+    }
+
+    fn prev_boundary_of(&self, pos: usize) -> usize {
+        self.value[..pos]
+            .graphemes(true)
+            .next_back()
+            .map_or(0, |g| pos - g.len())
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.value[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.value.len(), |g| self.cursor + g.len())
+    }
+
+    /// Start of the word before the cursor, words are separated by
+    /// whitespace.
+    fn word_start(&self) -> usize {
+        let mut pos = self.cursor;
+        let mut graphemes = self.value[..self.cursor].graphemes(true).rev().peekable();
+        while let Some(g) = graphemes.next_if(|g| is_space(g)) {
+            pos -= g.len();
+        }
+        while let Some(g) = graphemes.next_if(|g| !is_space(g)) {
+            pos -= g.len();
+        }
+        pos
+    }
+
+    fn word_end(&self) -> usize {
+        let mut pos = self.cursor;
+        let mut graphemes = self.value[self.cursor..].graphemes(true).peekable();
+        while let Some(g) = graphemes.next_if(|g| is_space(g)) {
+            pos += g.len();
+        }
+        while let Some(g) = graphemes.next_if(|g| !is_space(g)) {
+            pos += g.len();
+        }
+        pos
+    }
+
+    /// Shows the entry before the current one, the typed line is kept
+    /// until walking back past the newest entry.
+    pub fn history_prev(&mut self) {
+        let pos = match self.history_pos {
+            Some(0) => return,
+            Some(pos) => pos - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = std::mem::take(&mut self.value);
+                self.history.len() - 1
+            }
+        };
+        self.value = self.history[pos].clone();
+        self.cursor = self.value.len();
+        self.history_pos = Some(pos);
+    }
+
+    pub fn history_next(&mut self) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        self.value = if pos + 1 < self.history.len() {
+            self.history_pos = Some(pos + 1);
+            self.history[pos + 1].clone()
+        } else {
+            self.history_pos = None;
+            std::mem::take(&mut self.draft)
+        };
+        self.cursor = self.value.len();
+    }
+}
+
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Scrolls horizontally so the cursor stays visible.
+impl WidgetRef for TextInput {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let cursor_style = Style::new().add_modifier(Modifier::REVERSED);
+
+        if self.value.is_empty() {
+            let mut placeholder = self.placeholder.graphemes(true);
+            let first = placeholder.next().unwrap_or(" ");
+            Line::from(vec![
+                Span::styled(first, self.placeholder_style.patch(cursor_style)),
+                Span::styled(placeholder.as_str(), self.placeholder_style),
+            ])
+            .render(area, buf);
+            return;
+        }
+
+        let (before, after) = self.value.split_at(self.cursor);
+        let mut after = after.graphemes(true);
+        let under_cursor = after.next().unwrap_or(" ");
+
+        // Drops graphemes from the start until text up to and including the
+        // cursor fits.
+        let mut before = before;
+        let width = area.width as usize;
+        while !before.is_empty() && before.width() + under_cursor.width().max(1) > width {
+            let first = before.graphemes(true).next().unwrap_or_default();
+            before = &before[first.len()..];
+        }
+
+        Line::from(vec![
+            Span::raw(before),
+            Span::styled(under_cursor, cursor_style),
+            Span::raw(after.as_str()),
+        ])
+        .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(value: &str, cursor: usize) -> TextInput {
+        let mut input = TextInput::new(&Theme::dark());
+        input.set_value(value);
+        input.cursor = cursor;
+        input
+    }
+
+    fn press(input: &mut TextInput, key: Key) -> (&str, usize) {
+        input.event(key);
+        (input.value(), input.cursor)
+    }
+
+    #[test]
+    fn backspace() {
+        assert_eq!(press(&mut input("pa", 0), Key::Backspace), ("pa", 0));
+        assert_eq!(press(&mut input("pa", 1), Key::Backspace), ("a", 0));
+        assert_eq!(press(&mut input("pa", 2), Key::Backspace), ("p", 1));
+    }
+
+    #[test]
+    fn delete() {
+        assert_eq!(press(&mut input("pa", 0), Key::Delete), ("a", 0));
+        assert_eq!(press(&mut input("pa", 1), Key::Delete), ("p", 1));
+        assert_eq!(press(&mut input("pa", 2), Key::Delete), ("pa", 2));
+    }
+
+    #[test]
+    fn multi_codepoint_graphemes() {
+        // `e` with a combining acute accent, and a family emoji joined by
+        // zero width joiners.
+        let accent = "e\u{301}";
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let value = format!("a{accent}{family}b");
+        let end = value.len();
+
+        let mut at_end = input(&value, end - 1);
+        assert_eq!(
+            press(&mut at_end, Key::Backspace),
+            (format!("a{accent}b").as_str(), 1 + accent.len())
+        );
+        assert_eq!(press(&mut at_end, Key::Backspace), ("ab", 1));
+
+        let mut at_start = input(&value, 1);
+        assert_eq!(
+            press(&mut at_start, Key::Delete),
+            (format!("a{family}b").as_str(), 1)
+        );
+        assert_eq!(press(&mut at_start, Key::Delete), ("ab", 1));
+
+        let mut whole = input(&value, end);
+        for _ in 0..4 {
+            whole.event(Key::Backspace);
+        }
+        assert_eq!((whole.value(), whole.cursor), ("", 0));
+    }
+
+    #[test]
+    fn delete_word_before_cursor() {
+        let mut line = input("play  some song", 10);
+        assert_eq!(press(&mut line, Key::Ctrl('w')), ("play   song", 6));
+        // Spaces before the word go with it.
+        assert_eq!(press(&mut line, Key::Ctrl('w')), (" song", 0));
+        assert_eq!(press(&mut line, Key::Ctrl('w')), (" song", 0));
+    }
+
+    #[test]
+    fn kill_and_yank() {
+        let mut line = input("play some song", 4);
+        assert_eq!(press(&mut line, Key::Ctrl('k')), ("play", 4));
+        assert_eq!(press(&mut line, Key::Ctrl('a')), ("play", 0));
+        assert_eq!(press(&mut line, Key::Ctrl('y')), (" some songplay", 10));
+
+        let mut line = input("play some song", 9);
+        assert_eq!(press(&mut line, Key::Ctrl('u')), (" song", 0));
+        assert_eq!(press(&mut line, Key::End), (" song", 5));
+        assert_eq!(press(&mut line, Key::Ctrl('y')), (" songplay some", 14));
+
+        // Each kill replaces what was killed before.
+        let mut line = input("a b", 3);
+        press(&mut line, Key::Ctrl('w'));
+        press(&mut line, Key::Ctrl('w'));
+        assert_eq!(press(&mut line, Key::Ctrl('y')), ("a ", 2));
+    }
+
+    #[test]
+    fn walk_history() {
+        let mut line = input("", 0);
+        assert!(!line.event(Key::Up).is_consumed());
+
+        line.push_history("first".to_string());
+        line.push_history("second".to_string());
+        line.push_history("first".to_string());
+        line.set_value("draft");
+
+        assert_eq!(press(&mut line, Key::Up), ("first", 5));
+        assert_eq!(press(&mut line, Key::Up), ("second", 6));
+        // Oldest entry stays put.
+        assert_eq!(press(&mut line, Key::Up), ("second", 6));
+        assert_eq!(press(&mut line, Key::Down), ("first", 5));
+        assert_eq!(press(&mut line, Key::Down), ("draft", 5));
+        assert_eq!(press(&mut line, Key::Down), ("draft", 5));
+    }
+}
@@ -1,9 +1,31 @@
 use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+/// Two clicks on the same row within this are a double click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// Rows moved by one wheel step.
+const WHEEL_STEP: usize = 3;
 
 pub struct VerticalScroll {
     pub y_offset: Cell<usize>,
     pub pos: Cell<usize>,
     pub going_down: Cell<bool>,
+    /// Where the list was last rendered, for mapping clicks to rows.
+    area: Cell<Rect>,
+    last_click: Cell<Option<(Instant, usize)>>,
+}
+
+/// What a mouse event did to the list.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ListMouse {
+    /// Event wasn't over the list.
+    Missed,
+    Scrolled,
+    Clicked(usize),
+    DoubleClicked(usize),
 }
 
 impl VerticalScroll {
@@ -12,6 +34,8 @@ impl VerticalScroll {
             y_offset: Cell::new(0),
             pos: Cell::new(0),
             going_down: Cell::new(true),
+            area: Cell::new(Rect::default()),
+            last_click: Cell::new(None),
         }
     }
 
@@ -27,18 +51,86 @@ impl VerticalScroll {
 
     pub fn move_down(&self, max_len: usize) {
         let pos = self.pos.get();
-        if pos < max_len - 1 {
+        if pos + 1 < max_len {
             self.pos.set(pos + 1);
         }
         self.going_down.set(true);
     }
 
-    pub fn update(&self, visible_height: usize, max_selection: usize) {
-        let new_y_offset = self.calc_scroll_offset(
-            visible_height,
-            self.pos.get(),
-            max_selection,
+    /// Selects `pos` and scrolls it to the middle of the list, unless the
+    /// list ends before.
+    pub fn jump(&self, pos: usize, len: usize) {
+        let height = self.page_height();
+        self.pos.set(pos);
+        self.y_offset.set(
+            pos.saturating_sub(height / 2)
+                .min(len.saturating_sub(height)),
         );
+    }
+
+    /// Same as [`VerticalScroll::update`], also remembers `area` for mouse
+    /// handling.
+    pub fn update_area(&self, area: Rect, max_selection: usize) {
+        self.area.set(area);
+        self.update(area.height as usize, max_selection);
+    }
+
+    /// Rows shown in the last render, at least one.
+    pub fn page_height(&self) -> usize {
+        (self.area.get().height as usize).max(1)
+    }
+
+    /// Wheel moves selection, clicks select the row under the cursor. Only
+    /// works for lists rendered with [`VerticalScroll::update_area`].
+    pub fn mouse(&self, mouse: MouseEvent, len: usize) -> ListMouse {
+        let area = self.area.get();
+        if !area.contains(Position::new(mouse.column, mouse.row)) {
+            return ListMouse::Missed;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollDown => {
+                for _ in 0..WHEEL_STEP {
+                    if len > 0 {
+                        self.move_down(len);
+                    }
+                }
+                ListMouse::Scrolled
+            }
+            MouseEventKind::ScrollUp => {
+                for _ in 0..WHEEL_STEP {
+                    self.move_up();
+                }
+                ListMouse::Scrolled
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let index = self.y_offset.get() + (mouse.row - area.y) as usize;
+                if index >= len {
+                    return ListMouse::Missed;
+                }
+
+                self.going_down.set(index > self.pos.get());
+                self.pos.set(index);
+
+                let now = Instant::now();
+                let double = self
+                    .last_click
+                    .get()
+                    .is_some_and(|(at, i)| i == index && now - at <= DOUBLE_CLICK);
+                if double {
+                    self.last_click.set(None);
+                    ListMouse::DoubleClicked(index)
+                } else {
+                    self.last_click.set(Some((now, index)));
+                    ListMouse::Clicked(index)
+                }
+            }
+            _ => ListMouse::Missed,
+        }
+    }
+
+    pub fn update(&self, visible_height: usize, max_selection: usize) {
+        let new_y_offset = self.calc_scroll_offset(visible_height, self.pos.get(), max_selection);
 
         self.y_offset.set(new_y_offset);
 
@@ -74,6 +166,12 @@ impl VerticalScroll {
             return max_selection;
         }
 
+        // Area may have shrunk since the last render, e.g. after a terminal
+        // resize, keep selection on screen whichever way it last moved.
+        if selection >= y_offset + visible_height {
+            return selection + 1 - visible_height;
+        }
+
         if self.going_down.get() {
             if selection > visible_height + y_offset - 1 - pad
                 && max_selection > y_offset + visible_height
@@ -84,7 +182,7 @@ impl VerticalScroll {
             }
         } else {
             if selection < y_offset.saturating_add(pad) {
-                y_offset.saturating_sub(1)
+                selection.saturating_sub(pad)
             } else {
                 y_offset
             }
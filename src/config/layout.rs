@@ -0,0 +1,137 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+
+/// View that can be put in a pane.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Panel {
+    Tracklist,
+    /// Artists to narrow the tracklist down to.
+    Browser,
+    Stats,
+    Lyrics,
+    Queue,
+}
+
+impl Panel {
+    pub fn name(self) -> &'static str {
+        match self {
+            Panel::Tracklist => "Tracklist",
+            Panel::Browser => "Browser",
+            Panel::Stats => "Stats",
+            Panel::Lyrics => "Lyrics",
+            Panel::Queue => "Queue",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlsPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// Column of the main area.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Pane {
+    /// Views the pane switches between, the first one is shown at start.
+    pub panels: Vec<Panel>,
+    /// Width relative to other panes.
+    #[serde(default = "Pane::default_width")]
+    pub width: u16,
+    /// Pane collapses on terminals narrower than this, its focused view
+    /// moves to the first pane.
+    #[serde(default)]
+    pub min_terminal_width: u16,
+}
+
+impl Pane {
+    fn default_width() -> u16 {
+        1
+    }
+}
+
+/// Panes left to right with player controls above or below them, e.g. the
+/// browser on the left, the tracklist in the middle and queue or lyrics on
+/// the right:
+///
+/// ```toml
+/// [[layout.panes]]
+/// panels = ["browser"]
+/// min_terminal_width = 120
+///
+/// [[layout.panes]]
+/// panels = ["tracklist", "stats"]
+/// width = 3
+///
+/// [[layout.panes]]
+/// panels = ["queue", "lyrics"]
+/// width = 2
+/// min_terminal_width = 80
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub controls: ControlsPosition,
+    pub panes: Vec<Pane>,
+    /// Cover next to the controls is hidden on terminals narrower than
+    /// this.
+    pub cover_min_terminal_width: u16,
+    /// Percent of the main area a pane grows or shrinks by per key press.
+    pub resize_step: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            controls: ControlsPosition::Bottom,
+            panes: vec![Pane {
+                panels: vec![
+                    Panel::Tracklist,
+                    Panel::Browser,
+                    Panel::Stats,
+                    Panel::Lyrics,
+                    Panel::Queue,
+                ],
+                width: Pane::default_width(),
+                min_terminal_width: 0,
+            }],
+            cover_min_terminal_width: 60,
+            resize_step: 5,
+        }
+    }
+}
+
+impl LayoutConfig {
+    /// Checks what serde can't, the rest of the app relies on there being
+    /// at least one pane and every pane having a view.
+    pub fn validate(&self) -> Result<()> {
+        if self.panes.is_empty() {
+            return Err(eyre!("Layout needs at least one pane"));
+        }
+
+        let mut seen = vec![];
+        for pane in self.panes.iter() {
+            if pane.panels.is_empty() {
+                return Err(eyre!("Every pane in the layout needs at least one panel"));
+            }
+            if pane.width == 0 {
+                return Err(eyre!("Pane width has to be at least 1"));
+            }
+            for panel in pane.panels.iter() {
+                if seen.contains(panel) {
+                    return Err(eyre!(
+                        "Panel {} is in more than one pane",
+                        panel.name().to_lowercase()
+                    ));
+                }
+                seen.push(*panel);
+            }
+        }
+
+        Ok(())
+    }
+}
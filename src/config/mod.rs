@@ -0,0 +1,326 @@
+mod layout;
+mod theme;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+
+use crate::backend::BackendKind;
+use crate::components::cover_art::GraphicsProtocol;
+use crate::event::Key;
+
+pub use layout::{ControlsPosition, LayoutConfig, Panel};
+pub use theme::{ColorSupport, Theme};
+
+#[derive(Default)]
+pub struct Config {
+    pub audio_dir: PathBuf,
+    pub audio_device: Option<String>,
+    pub audio_backend: BackendKind,
+    /// Tracks at least this long remember where they were left off.
+    pub resume_min_duration: Duration,
+    /// How often player state is saved while running, it's also saved on
+    /// exit.
+    pub session_save_interval: Duration,
+    /// How often the audio thread reports playback position.
+    pub position_update_interval: Duration,
+    /// Also store ratings in the files' tags so other players see them.
+    pub write_ratings_to_files: bool,
+    /// How cover art is drawn, detected from the terminal when `None`.
+    pub graphics_protocol: Option<GraphicsProtocol>,
+    pub tick_rate: Duration,
+    /// Slower tick rate used while the terminal is unfocused, ticks don't
+    /// slow down when `None`.
+    pub unfocused_tick_rate: Option<Duration>,
+    pub key_config: KeyConfig,
+    pub theme: Theme,
+    /// Tracklist cursor follows the playing track as the queue advances.
+    pub follow_playback: bool,
+    pub layout: LayoutConfig,
+}
+
+/// Shorter intervals keep the audio thread waking up for nothing, 0 would
+/// spin it.
+const MIN_POSITION_UPDATE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Subset of [`Config`] that can be overridden from `config.toml`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ConfigFile {
+    audio_dir: Option<PathBuf>,
+    audio_device: Option<String>,
+    audio_backend: Option<BackendKind>,
+    /// In seconds.
+    resume_min_duration: Option<u64>,
+    /// In seconds.
+    session_save_interval: Option<u64>,
+    /// In milliseconds, at least [`MIN_POSITION_UPDATE_INTERVAL`].
+    position_update_interval: Option<u64>,
+    write_ratings_to_files: Option<bool>,
+    graphics_protocol: Option<GraphicsProtocol>,
+    /// In milliseconds.
+    tick_rate: Option<u64>,
+    /// In milliseconds.
+    unfocused_tick_rate: Option<u64>,
+    /// Bindings not listed keep their defaults.
+    keys: Option<KeyConfig>,
+    /// Built-in `dark` or `light`, name of a file in the themes directory
+    /// or a path to one.
+    theme: Option<String>,
+    /// Detected from the terminal when not set.
+    color_support: Option<ColorSupport>,
+    layout: Option<LayoutConfig>,
+    follow_playback: Option<bool>,
+}
+
+impl Config {
+    pub fn new(audio_dir: PathBuf) -> Self {
+        Config {
+            audio_dir,
+            audio_device: None,
+            audio_backend: BackendKind::default(),
+            resume_min_duration: Duration::from_secs(20 * 60),
+            session_save_interval: Duration::from_secs(30),
+            position_update_interval: Duration::from_millis(250),
+            write_ratings_to_files: false,
+            graphics_protocol: None,
+            tick_rate: Duration::from_millis(250),
+            unfocused_tick_rate: None,
+            key_config: KeyConfig::default(),
+            theme: Theme::dark().with_color_support(ColorSupport::detect()),
+            layout: LayoutConfig::default(),
+            follow_playback: false,
+        }
+    }
+
+    /// Same as [`Config::new`] but with values from the user's config file
+    /// applied on top, if the file exists.
+    pub fn load(audio_dir: PathBuf) -> Result<Self> {
+        let mut config = Config::new(audio_dir);
+
+        let Some(path) = Self::path() else {
+            return Ok(config);
+        };
+        if !path.exists() {
+            return Ok(config);
+        }
+
+        let file: ConfigFile = toml::from_str(&std::fs::read_to_string(path)?)?;
+        if let Some(audio_dir) = file.audio_dir {
+            config.audio_dir = audio_dir;
+        }
+        config.audio_device = file.audio_device;
+        if let Some(audio_backend) = file.audio_backend {
+            config.audio_backend = audio_backend;
+        }
+        if let Some(secs) = file.resume_min_duration {
+            config.resume_min_duration = Duration::from_secs(secs);
+        }
+        if let Some(secs) = file.session_save_interval {
+            config.session_save_interval = Duration::from_secs(secs);
+        }
+        if let Some(ms) = file.position_update_interval {
+            let interval = Duration::from_millis(ms);
+            if interval < MIN_POSITION_UPDATE_INTERVAL {
+                return Err(eyre!(
+                    "position_update_interval has to be at least {} ms, got {ms}",
+                    MIN_POSITION_UPDATE_INTERVAL.as_millis()
+                ));
+            }
+            config.position_update_interval = interval;
+        }
+        if let Some(write) = file.write_ratings_to_files {
+            config.write_ratings_to_files = write;
+        }
+        config.graphics_protocol = file.graphics_protocol;
+        if let Some(ms) = file.tick_rate {
+            config.tick_rate = Duration::from_millis(ms);
+        }
+        config.unfocused_tick_rate = file.unfocused_tick_rate.map(Duration::from_millis);
+        if let Some(keys) = file.keys {
+            config.key_config = keys;
+        }
+        let color_support = file.color_support.unwrap_or_else(ColorSupport::detect);
+        config.theme = Theme::load(
+            file.theme.as_deref().unwrap_or("dark"),
+            Self::themes_dir().as_deref(),
+            color_support,
+        )?;
+        if let Some(follow) = file.follow_playback {
+            config.follow_playback = follow;
+        }
+        if let Some(layout) = file.layout {
+            layout.validate()?;
+            config.layout = layout;
+        }
+
+        Ok(config)
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("mood").join("config.toml"))
+    }
+
+    /// Where theme files named in the config are looked up.
+    pub fn themes_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("mood").join("themes"))
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct KeyConfig {
+    pub quit: Key,
+
+    pub scroll_up: Key,
+    pub scroll_down: Key,
+
+    pub play_audio: Key,
+    pub add_to_manual_queue: Key,
+    pub cycle_sort: Key,
+    pub cycle_filter: Key,
+
+    pub rating_up: Key,
+    pub rating_down: Key,
+    pub toggle_favorite: Key,
+    pub playing_rating_up: Key,
+    pub playing_rating_down: Key,
+    pub playing_toggle_favorite: Key,
+
+    pub edit_tags: Key,
+    pub undo_tag_edit: Key,
+    pub next_field: Key,
+    pub prev_field: Key,
+    pub keep_existing: Key,
+    pub confirm: Key,
+
+    pub visual_select: Key,
+    pub toggle_select: Key,
+    pub select_all: Key,
+    pub invert_selection: Key,
+    pub clear_selection: Key,
+    pub add_to_playlist: Key,
+    pub remove_from_library: Key,
+    pub jump_to_playing: Key,
+    pub toggle_follow_playback: Key,
+
+    pub skip_to_next_audio: Key,
+    pub skip_to_prev_audio: Key,
+    pub seek_forward: Key,
+    pub seek_backward: Key,
+
+    pub pause: Key,
+    pub shuffle: Key,
+    pub repeat: Key,
+    pub volume_up: Key,
+    pub volume_down: Key,
+
+    pub pick_playlist: Key,
+
+    pub create_playlist: Key,
+    pub delete_playlist: Key,
+
+    pub focus_playlist_popup: Key,
+
+    pub pick_audio_device: Key,
+    pub close_popup: Key,
+
+    pub focus_browser: Key,
+    pub focus_stats: Key,
+    pub focus_lyrics: Key,
+    pub focus_queue: Key,
+    pub cycle_period: Key,
+
+    pub queue_move_up: Key,
+    pub queue_move_down: Key,
+    pub remove_from_queue: Key,
+    pub clear_queue: Key,
+    pub shuffle_queue: Key,
+
+    pub focus_next_pane: Key,
+    pub focus_prev_pane: Key,
+    pub grow_pane: Key,
+    pub shrink_pane: Key,
+
+    pub help: Key,
+    pub command_line: Key,
+    pub command_palette: Key,
+    pub complete: Key,
+    pub history_prev: Key,
+    pub history_next: Key,
+}
+
+impl Default for KeyConfig {
+    fn default() -> Self {
+        KeyConfig {
+            quit: Key::Esc,
+            scroll_up: Key::Char('k'),
+            scroll_down: Key::Char('j'),
+            play_audio: Key::Enter,
+            add_to_manual_queue: Key::Char('q'),
+            cycle_sort: Key::Char('S'),
+            cycle_filter: Key::Char('F'),
+            rating_up: Key::Char('>'),
+            rating_down: Key::Char('<'),
+            toggle_favorite: Key::Char('f'),
+            playing_rating_up: Key::Char(']'),
+            playing_rating_down: Key::Char('['),
+            playing_toggle_favorite: Key::Char('L'),
+            edit_tags: Key::Char('e'),
+            undo_tag_edit: Key::Char('u'),
+            // Up and Down walk the field's history, Ctrl-k kills to the end
+            // of the line.
+            next_field: Key::Tab,
+            prev_field: Key::BackTab,
+            keep_existing: Key::Ctrl('x'),
+            confirm: Key::Enter,
+            visual_select: Key::Char('V'),
+            toggle_select: Key::Char('v'),
+            select_all: Key::Ctrl('a'),
+            invert_selection: Key::Char('I'),
+            clear_selection: Key::Esc,
+            add_to_playlist: Key::Char('a'),
+            remove_from_library: Key::Char('X'),
+            jump_to_playing: Key::Char('c'),
+            toggle_follow_playback: Key::Char('C'),
+            skip_to_next_audio: Key::Char('l'),
+            skip_to_prev_audio: Key::Char('h'),
+            seek_forward: Key::Ctrl('l'),
+            seek_backward: Key::Ctrl('h'),
+            pause: Key::Char(' '),
+            shuffle: Key::Char('s'),
+            repeat: Key::Char('r'),
+            volume_up: Key::Char('+'),
+            volume_down: Key::Char('-'),
+            pick_playlist: Key::Enter,
+            create_playlist: Key::Char('n'),
+            delete_playlist: Key::Char('D'),
+            focus_playlist_popup: Key::Char('p'),
+            pick_audio_device: Key::Char('o'),
+            close_popup: Key::Esc,
+            focus_browser: Key::Char('b'),
+            focus_stats: Key::Char('i'),
+            focus_lyrics: Key::Char('y'),
+            focus_queue: Key::Char('Q'),
+            cycle_period: Key::Tab,
+            queue_move_up: Key::Char('K'),
+            queue_move_down: Key::Char('J'),
+            remove_from_queue: Key::Char('x'),
+            clear_queue: Key::Char('X'),
+            shuffle_queue: Key::Char('S'),
+            focus_next_pane: Key::Char('w'),
+            focus_prev_pane: Key::Char('W'),
+            grow_pane: Key::Char('}'),
+            shrink_pane: Key::Char('{'),
+            help: Key::Char('?'),
+            command_line: Key::Char(':'),
+            command_palette: Key::Ctrl('p'),
+            complete: Key::Tab,
+            history_prev: Key::Up,
+            history_next: Key::Down,
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// Colors the terminal can show, anything richer is mapped down to it.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSupport {
+    #[default]
+    TrueColor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    /// Only modifiers like bold and reversed, see <https://no-color.org>.
+    None,
+}
+
+impl ColorSupport {
+    /// Guesses from environment variables terminals set. `NO_COLOR` wins
+    /// over everything else.
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name| var(name).unwrap_or_default().to_lowercase();
+
+        if !var("NO_COLOR").is_empty() {
+            return ColorSupport::None;
+        }
+
+        let term = var("TERM");
+        match var("COLORTERM").as_str() {
+            "truecolor" | "24bit" => ColorSupport::TrueColor,
+            _ if term.contains("256color") => ColorSupport::Ansi256,
+            _ if term == "dumb" => ColorSupport::None,
+            _ => ColorSupport::Ansi16,
+        }
+    }
+}
+
+/// Named styles components draw with.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Row under the cursor in lists.
+    pub selection: Style,
+    /// Rows picked for bulk operations.
+    pub marked: Style,
+    /// Track that's playing, wherever it's listed.
+    pub playing: Style,
+    pub border: Style,
+    /// Border of the panel or popup keys go to.
+    pub focused_border: Style,
+    /// Filled part of progress and volume bars in the foreground, empty part
+    /// in the background.
+    pub progress: Style,
+    pub error: Style,
+    /// Border of popups asking to confirm something.
+    pub warning: Style,
+    /// Hints, placeholders and things that are off.
+    pub dim: Style,
+    /// Values and things that are on.
+    pub accent: Style,
+    /// Section headers and the current line of lyrics.
+    pub heading: Style,
+    /// Listening heatmap, from no listening to the most.
+    pub heatmap: [Color; 5],
+    color_support: ColorSupport,
+}
+
+/// Theme file from the themes directory, styles not listed come from
+/// `base`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ThemeFile {
+    /// Built-in theme to start from, dark when not set.
+    base: Option<String>,
+    selection: Option<StyleFile>,
+    marked: Option<StyleFile>,
+    playing: Option<StyleFile>,
+    border: Option<StyleFile>,
+    focused_border: Option<StyleFile>,
+    progress: Option<StyleFile>,
+    error: Option<StyleFile>,
+    warning: Option<StyleFile>,
+    dim: Option<StyleFile>,
+    accent: Option<StyleFile>,
+    heading: Option<StyleFile>,
+    heatmap: Option<[String; 5]>,
+}
+
+/// Colors are names like `lightblue`, `#rrggbb` or 256 color indices.
+#[derive(Deserialize)]
+struct StyleFile {
+    fg: Option<String>,
+    bg: Option<String>,
+    /// Replaces modifiers of the base style, e.g. `["bold", "italic"]`.
+    modifiers: Option<Vec<String>>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Theme {
+            selection: Style::new().bg(Color::Blue),
+            marked: Style::new().bg(Color::DarkGray),
+            playing: Style::new().fg(Color::Green).add_modifier(Modifier::BOLD),
+            border: Style::new(),
+            focused_border: Style::new().fg(Color::Cyan),
+            progress: Style::new().fg(Color::Green).bg(Color::DarkGray),
+            error: Style::new().fg(Color::Red),
+            warning: Style::new().fg(Color::Yellow),
+            dim: Style::new().fg(Color::DarkGray),
+            accent: Style::new().fg(Color::Green),
+            heading: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            heatmap: [
+                Color::DarkGray,
+                Color::Rgb(14, 68, 41),
+                Color::Rgb(0, 109, 50),
+                Color::Rgb(38, 166, 65),
+                Color::Rgb(57, 211, 83),
+            ],
+            color_support: ColorSupport::TrueColor,
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            selection: Style::new().fg(Color::Black).bg(Color::LightBlue),
+            marked: Style::new().bg(Color::Gray),
+            playing: Style::new()
+                .fg(Color::Rgb(0, 128, 0))
+                .add_modifier(Modifier::BOLD),
+            border: Style::new(),
+            focused_border: Style::new().fg(Color::Blue),
+            progress: Style::new().fg(Color::Blue).bg(Color::Gray),
+            error: Style::new().fg(Color::Red),
+            warning: Style::new().fg(Color::Rgb(176, 112, 0)),
+            dim: Style::new().fg(Color::DarkGray),
+            accent: Style::new().fg(Color::Rgb(0, 128, 0)),
+            heading: Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            heatmap: [
+                Color::Gray,
+                Color::Rgb(155, 233, 168),
+                Color::Rgb(64, 196, 99),
+                Color::Rgb(48, 161, 78),
+                Color::Rgb(33, 110, 57),
+            ],
+            color_support: ColorSupport::TrueColor,
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    /// `name` is a built-in theme, a file in `themes_dir` without the
+    /// `.toml` extension or a path to a theme file.
+    pub fn load(
+        name: &str,
+        themes_dir: Option<&Path>,
+        color_support: ColorSupport,
+    ) -> Result<Self> {
+        if let Some(theme) = Theme::builtin(name) {
+            return Ok(theme.with_color_support(color_support));
+        }
+
+        let path = if name.ends_with(".toml") || name.contains(std::path::MAIN_SEPARATOR) {
+            PathBuf::from(name)
+        } else {
+            themes_dir
+                .ok_or(eyre!("No themes directory to look for theme \"{name}\" in"))?
+                .join(format!("{name}.toml"))
+        };
+        let file: ThemeFile = toml::from_str(
+            &std::fs::read_to_string(&path)
+                .map_err(|e| eyre!("Can't read theme {}: {e}", path.display()))?,
+        )?;
+
+        let base = file.base.as_deref().unwrap_or("dark");
+        let mut theme = Theme::builtin(base)
+            .ok_or(eyre!("Unknown base theme \"{base}\", use dark or light"))?;
+
+        apply(&mut theme.selection, file.selection)?;
+        apply(&mut theme.marked, file.marked)?;
+        apply(&mut theme.playing, file.playing)?;
+        apply(&mut theme.border, file.border)?;
+        apply(&mut theme.focused_border, file.focused_border)?;
+        apply(&mut theme.progress, file.progress)?;
+        apply(&mut theme.error, file.error)?;
+        apply(&mut theme.warning, file.warning)?;
+        apply(&mut theme.dim, file.dim)?;
+        apply(&mut theme.accent, file.accent)?;
+        apply(&mut theme.heading, file.heading)?;
+        if let Some(heatmap) = file.heatmap {
+            for (color, name) in theme.heatmap.iter_mut().zip(heatmap) {
+                *color = parse_color(&name)?;
+            }
+        }
+
+        Ok(theme.with_color_support(color_support))
+    }
+
+    /// Maps colors down to what the terminal shows. Without colors the
+    /// styles that only differ by background fall back to modifiers.
+    pub fn with_color_support(mut self, color_support: ColorSupport) -> Self {
+        self.color_support = color_support;
+
+        if color_support == ColorSupport::None {
+            self.selection = Style::new().add_modifier(Modifier::REVERSED);
+            self.marked = Style::new().add_modifier(Modifier::BOLD | Modifier::ITALIC);
+            self.focused_border = Style::new().add_modifier(Modifier::BOLD);
+        }
+
+        for style in [
+            &mut self.selection,
+            &mut self.marked,
+            &mut self.playing,
+            &mut self.border,
+            &mut self.focused_border,
+            &mut self.progress,
+            &mut self.error,
+            &mut self.warning,
+            &mut self.dim,
+            &mut self.accent,
+            &mut self.heading,
+        ] {
+            style.fg = style.fg.and_then(|c| downsample(c, color_support));
+            style.bg = style.bg.and_then(|c| downsample(c, color_support));
+        }
+        for color in self.heatmap.iter_mut() {
+            *color = downsample(*color, color_support).unwrap_or(Color::Reset);
+        }
+
+        self
+    }
+
+    /// `color` as the terminal can show it, for colors that don't come from
+    /// the theme like cover art.
+    pub fn color(&self, color: Color) -> Color {
+        downsample(color, self.color_support).unwrap_or(Color::Reset)
+    }
+
+    pub fn has_colors(&self) -> bool {
+        self.color_support != ColorSupport::None
+    }
+}
+
+fn apply(style: &mut Style, file: Option<StyleFile>) -> Result<()> {
+    let Some(file) = file else {
+        return Ok(());
+    };
+
+    if let Some(fg) = file.fg {
+        style.fg = Some(parse_color(&fg)?);
+    }
+    if let Some(bg) = file.bg {
+        style.bg = Some(parse_color(&bg)?);
+    }
+    if let Some(modifiers) = file.modifiers {
+        style.add_modifier = Modifier::empty();
+        for name in modifiers {
+            style.add_modifier |= parse_modifier(&name)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_color(name: &str) -> Result<Color> {
+    Color::from_str(name).map_err(|_| eyre!("Unknown color \"{name}\""))
+}
+
+fn parse_modifier(name: &str) -> Result<Modifier> {
+    Ok(match name.to_lowercase().as_str() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" => Modifier::UNDERLINED,
+        "slow_blink" => Modifier::SLOW_BLINK,
+        "rapid_blink" => Modifier::RAPID_BLINK,
+        "reversed" => Modifier::REVERSED,
+        "hidden" => Modifier::HIDDEN,
+        "crossed_out" => Modifier::CROSSED_OUT,
+        _ => return Err(eyre!("Unknown modifier \"{name}\"")),
+    })
+}
+
+/// xterm's default 16 colors.
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Steps of each channel in the 6x6x6 color cube of 256 color terminals.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Closest color the terminal has, `None` without colors.
+fn downsample(color: Color, color_support: ColorSupport) -> Option<Color> {
+    Some(match (color_support, color) {
+        (ColorSupport::None, _) => return None,
+        (ColorSupport::TrueColor, c) => c,
+        (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => {
+            let step = |c: u8| {
+                CUBE.iter()
+                    .enumerate()
+                    .min_by_key(|(_, s)| (**s as i32 - c as i32).abs())
+                    .map_or(0, |(i, _)| i as u8)
+            };
+            Color::Indexed(16 + 36 * step(r) + 6 * step(g) + step(b))
+        }
+        (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi((r, g, b)),
+        (ColorSupport::Ansi16, Color::Indexed(i)) => match ANSI.get(i as usize) {
+            Some((c, _)) => *c,
+            None => nearest_ansi(indexed_rgb(i)),
+        },
+        (_, c) => c,
+    })
+}
+
+fn nearest_ansi((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(ar, ag, ab): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(ar, r) + d(ag, g) + d(ab, b)
+    };
+    ANSI.iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or(Color::Reset, |(c, _)| *c)
+}
+
+/// RGB of a 256 color index past the first 16.
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    if i >= 232 {
+        let gray = 8 + 10 * (i - 232);
+        (gray, gray, gray)
+    } else {
+        let i = i - 16;
+        (
+            CUBE[(i / 36) as usize],
+            CUBE[(i / 6 % 6) as usize],
+            CUBE[(i % 6) as usize],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| {
+            vars.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    /// Theme file in a fresh directory, removed on drop.
+    struct ThemeDir(PathBuf);
+
+    impl ThemeDir {
+        fn new(name: &str, content: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("mood-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(format!("{name}.toml")), content).unwrap();
+            ThemeDir(dir)
+        }
+
+        fn load(&self, name: &str) -> Result<Theme> {
+            Theme::load(name, Some(&self.0), ColorSupport::TrueColor)
+        }
+    }
+
+    impl Drop for ThemeDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn detect_from_env() {
+        let detect = |vars: &[(&str, &str)]| ColorSupport::from_env(env(vars));
+
+        assert_eq!(detect(&[]), ColorSupport::Ansi16);
+        assert_eq!(
+            detect(&[("COLORTERM", "truecolor")]),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(detect(&[("COLORTERM", "24BIT")]), ColorSupport::TrueColor);
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), ColorSupport::Ansi256);
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorSupport::None);
+        assert_eq!(
+            detect(&[("NO_COLOR", "1"), ("COLORTERM", "truecolor")]),
+            ColorSupport::None
+        );
+        assert_eq!(detect(&[("NO_COLOR", "")]), ColorSupport::Ansi16);
+    }
+
+    #[test]
+    fn downsample_to_support() {
+        let rgb = Color::Rgb(250, 10, 10);
+
+        assert_eq!(downsample(rgb, ColorSupport::TrueColor), Some(rgb));
+        assert_eq!(downsample(rgb, ColorSupport::None), None);
+        assert_eq!(downsample(Color::Red, ColorSupport::None), None);
+        // Red is index 196 in the color cube.
+        assert_eq!(
+            downsample(rgb, ColorSupport::Ansi256),
+            Some(Color::Indexed(196))
+        );
+        assert_eq!(
+            downsample(Color::Rgb(0, 0, 0), ColorSupport::Ansi256),
+            Some(Color::Indexed(16))
+        );
+        assert_eq!(downsample(rgb, ColorSupport::Ansi16), Some(Color::LightRed));
+        assert_eq!(
+            downsample(Color::Indexed(4), ColorSupport::Ansi16),
+            Some(Color::Blue)
+        );
+        // Lightest gray of the ramp is 238.
+        assert_eq!(
+            downsample(Color::Indexed(255), ColorSupport::Ansi16),
+            Some(Color::Gray)
+        );
+        assert_eq!(
+            downsample(Color::Green, ColorSupport::Ansi16),
+            Some(Color::Green)
+        );
+    }
+
+    #[test]
+    fn nearest_ansi_color() {
+        assert_eq!(nearest_ansi((0, 0, 0)), Color::Black);
+        assert_eq!(nearest_ansi((255, 255, 255)), Color::White);
+        assert_eq!(nearest_ansi((120, 130, 125)), Color::DarkGray);
+        assert_eq!(nearest_ansi((0, 190, 210)), Color::Cyan);
+        assert_eq!(nearest_ansi((80, 80, 250)), Color::LightBlue);
+    }
+
+    #[test]
+    fn no_colors_fall_back_to_modifiers() {
+        let theme = Theme::dark().with_color_support(ColorSupport::None);
+
+        assert!(!theme.has_colors());
+        assert_eq!(
+            theme.selection,
+            Style::new().add_modifier(Modifier::REVERSED)
+        );
+        assert_eq!(theme.playing.fg, None);
+        assert!(theme.playing.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(theme.heatmap, [Color::Reset; 5]);
+    }
+
+    #[test]
+    fn load_builtin_and_missing() {
+        let theme = Theme::load("light", None, ColorSupport::TrueColor).unwrap();
+        assert_eq!(theme.selection, Theme::light().selection);
+
+        assert!(Theme::load("mine", None, ColorSupport::TrueColor).is_err());
+        let dir = ThemeDir::new("mine", "");
+        assert!(dir.load("other").is_err());
+    }
+
+    #[test]
+    fn load_overrides_base() {
+        let dir = ThemeDir::new(
+            "mine",
+            r##"
+base = "light"
+heatmap = ["black", "red", "green", "yellow", "#ff00ff"]
+
+[playing]
+fg = "lightcyan"
+
+[selection]
+bg = "21"
+modifiers = ["italic", "UNDERLINED"]
+"##,
+        );
+        let theme = dir.load("mine").unwrap();
+        let light = Theme::light();
+
+        // Only fg is replaced, modifiers stay.
+        assert_eq!(theme.playing, light.playing.fg(Color::LightCyan));
+        assert_eq!(theme.selection.fg, light.selection.fg);
+        assert_eq!(theme.selection.bg, Some(Color::Indexed(21)));
+        assert_eq!(
+            theme.selection.add_modifier,
+            Modifier::ITALIC | Modifier::UNDERLINED
+        );
+        assert_eq!(theme.heatmap[4], Color::Rgb(255, 0, 255));
+        // Styles not in the file come from the base.
+        assert_eq!(theme.warning, light.warning);
+        assert_eq!(theme.heading, light.heading);
+
+        let path = dir.0.join("mine.toml");
+        let by_path = Theme::load(path.to_str().unwrap(), None, ColorSupport::TrueColor);
+        assert_eq!(by_path.unwrap().playing, theme.playing);
+    }
+
+    #[test]
+    fn load_errors() {
+        let error = |content: &str| {
+            ThemeDir::new("bad", content)
+                .load("bad")
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("[dim]\nfg = \"blurple\""),
+            "Unknown color \"blurple\""
+        );
+        assert_eq!(
+            error("[dim]\nmodifiers = [\"bold\", \"wavy\"]"),
+            "Unknown modifier \"wavy\""
+        );
+        assert_eq!(
+            error("heatmap = [\"black\", \"red\", \"green\", \"blue\", \"nope\"]"),
+            "Unknown color \"nope\""
+        );
+        assert_eq!(
+            error("base = \"solarized\""),
+            "Unknown base theme \"solarized\", use dark or light"
+        );
+    }
+}
@@ -1,15 +1,34 @@
-use std::{path::PathBuf, time::Duration};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use uuid::Uuid;
+
+use crate::models::PlayContext;
 
 pub struct CurrentTrack {
+    pub uuid: Uuid,
     pub path: PathBuf,
     pub total_duration: Duration,
+
+    pub started_at: SystemTime,
+    /// Time actually listened, seeks and pauses excluded.
+    pub listened: Duration,
+    /// Audio thread reported starting it, progress reported before that is
+    /// of the previous track.
+    pub audio_started: bool,
+    pub context: PlayContext,
 }
 
 impl CurrentTrack {
-    pub fn new(path: PathBuf, total_duration: Duration) -> Self {
+    pub fn new(uuid: Uuid, path: PathBuf, total_duration: Duration, context: PlayContext) -> Self {
         CurrentTrack {
+            uuid,
             path,
             total_duration,
+            started_at: SystemTime::now(),
+            listened: Duration::ZERO,
+            audio_started: false,
+            context,
         }
     }
 
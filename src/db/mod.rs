@@ -0,0 +1,499 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use rusqlite::{Connection, OptionalExtension, params};
+use uuid::Uuid;
+
+use crate::models::{PlayContext, Playlist, TrackStats};
+use crate::queue::Repeat;
+
+/// Where the database lives, next to nothing else the user edits.
+pub fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("mood").join("mood.db3"))
+}
+
+/// Opens the database at [`path`], creating its directory if needed.
+pub fn open() -> Result<Connection> {
+    let path = path().ok_or_else(|| eyre!("No data directory to keep the database in"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    Ok(Connection::open(path)?)
+}
+
+/// Creates tables that don't exist yet.
+pub fn init(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS session (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            track_uuid TEXT,
+            pos_ms INTEGER NOT NULL,
+            volume REAL NOT NULL,
+            shuffle INTEGER NOT NULL,
+            manual_queue TEXT NOT NULL,
+            context TEXT NOT NULL,
+            context_pos INTEGER NOT NULL,
+            repeat TEXT NOT NULL DEFAULT 'off'
+        );
+        CREATE TABLE IF NOT EXISTS resume_position (
+            track_uuid TEXT PRIMARY KEY,
+            pos_ms INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS history (
+            id INTEGER PRIMARY KEY,
+            track_uuid TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            listened_ms INTEGER NOT NULL,
+            skipped INTEGER NOT NULL,
+            context TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS history_track ON history (track_uuid);
+        CREATE INDEX IF NOT EXISTS history_started_at ON history (started_at);
+        CREATE TABLE IF NOT EXISTS playlist (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS playlist_track (
+            playlist_id INTEGER NOT NULL REFERENCES playlist (id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            track_uuid TEXT NOT NULL,
+            PRIMARY KEY (playlist_id, position)
+        );
+        CREATE TABLE IF NOT EXISTS removed_track (
+            track_uuid TEXT PRIMARY KEY
+        );
+        CREATE TABLE IF NOT EXISTS rating (
+            track_uuid TEXT PRIMARY KEY,
+            stars INTEGER NOT NULL,
+            favorite INTEGER NOT NULL
+        );",
+    )?;
+
+    // Sessions saved before repeat was.
+    let has_repeat: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info('session') WHERE name = 'repeat')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_repeat {
+        conn.execute(
+            "ALTER TABLE session ADD COLUMN repeat TEXT NOT NULL DEFAULT 'off'",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Player state at the moment mood was closed.
+pub struct Session {
+    pub track: Option<Uuid>,
+    pub pos: Duration,
+    pub volume: f32,
+    pub shuffle: bool,
+    pub manual_queue: VecDeque<Uuid>,
+    pub context: Vec<Uuid>,
+    pub context_pos: usize,
+    pub repeat: Repeat,
+}
+
+pub fn save_session(conn: &Connection, session: &Session) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO session
+            (id, track_uuid, pos_ms, volume, shuffle, manual_queue, context, context_pos, repeat)
+        VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            session.track.map(|u| u.to_string()),
+            session.pos.as_millis() as i64,
+            session.volume,
+            session.shuffle,
+            join_uuids(&session.manual_queue),
+            join_uuids(&session.context),
+            session.context_pos as i64,
+            session.repeat.name(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn load_session(conn: &Connection) -> Result<Option<Session>> {
+    let session = conn
+        .query_row(
+            "SELECT track_uuid, pos_ms, volume, shuffle, manual_queue, context, context_pos, repeat
+            FROM session WHERE id = 1",
+            [],
+            |row| {
+                Ok(Session {
+                    track: row
+                        .get::<_, Option<String>>(0)?
+                        .and_then(|u| Uuid::parse_str(&u).ok()),
+                    pos: Duration::from_millis(row.get::<_, i64>(1)? as u64),
+                    volume: row.get(2)?,
+                    shuffle: row.get(3)?,
+                    manual_queue: split_uuids(&row.get::<_, String>(4)?),
+                    context: split_uuids(&row.get::<_, String>(5)?),
+                    context_pos: row.get::<_, i64>(6)? as usize,
+                    repeat: row.get::<_, String>(7)?.parse().unwrap_or_default(),
+                })
+            },
+        )
+        .optional()?;
+    Ok(session)
+}
+
+pub fn save_resume_position(conn: &Connection, track: Uuid, pos: Duration) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO resume_position (track_uuid, pos_ms) VALUES (?1, ?2)",
+        params![track.to_string(), pos.as_millis() as i64],
+    )?;
+    Ok(())
+}
+
+pub fn resume_position(conn: &Connection, track: Uuid) -> Result<Option<Duration>> {
+    let pos = conn
+        .query_row(
+            "SELECT pos_ms FROM resume_position WHERE track_uuid = ?1",
+            params![track.to_string()],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()?;
+    Ok(pos.map(|ms| Duration::from_millis(ms as u64)))
+}
+
+pub fn clear_resume_position(conn: &Connection, track: Uuid) -> Result<()> {
+    conn.execute(
+        "DELETE FROM resume_position WHERE track_uuid = ?1",
+        params![track.to_string()],
+    )?;
+    Ok(())
+}
+
+/// Single playback of a track.
+pub struct Play {
+    pub track: Uuid,
+    pub started_at: SystemTime,
+    pub listened: Duration,
+    pub skipped: bool,
+    pub context: PlayContext,
+}
+
+pub fn record_play(conn: &Connection, play: &Play) -> Result<()> {
+    conn.execute(
+        "INSERT INTO history (track_uuid, started_at, listened_ms, skipped, context)
+        VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            play.track.to_string(),
+            to_unix(play.started_at),
+            play.listened.as_millis() as i64,
+            play.skipped,
+            play.context.to_string(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Plays started at or after `since`, all of them if `None`.
+pub fn history(conn: &Connection, since: Option<SystemTime>) -> Result<Vec<Play>> {
+    let mut stmt = conn.prepare(
+        "SELECT track_uuid, started_at, listened_ms, skipped, context
+        FROM history WHERE started_at >= ?1 ORDER BY started_at",
+    )?;
+
+    let since = since.map(to_unix).unwrap_or(0);
+    let plays = stmt
+        .query_map(params![since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?
+        .filter_map(|row| {
+            let (uuid, started_at, listened_ms, skipped, context) = row.ok()?;
+            Some(Play {
+                track: Uuid::parse_str(&uuid).ok()?,
+                started_at: from_unix(started_at),
+                listened: Duration::from_millis(listened_ms as u64),
+                skipped,
+                context: context.parse().ok()?,
+            })
+        })
+        .collect();
+
+    Ok(plays)
+}
+
+/// Play count, skip count and last play time of every track ever played.
+pub fn track_stats(conn: &Connection) -> Result<HashMap<Uuid, TrackStats>> {
+    let mut stmt = conn.prepare(
+        "SELECT track_uuid, SUM(NOT skipped), SUM(skipped), MAX(started_at)
+        FROM history GROUP BY track_uuid",
+    )?;
+
+    let stats = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                TrackStats {
+                    play_count: row.get(1)?,
+                    skip_count: row.get(2)?,
+                    last_played: Some(from_unix(row.get(3)?)),
+                },
+            ))
+        })?
+        .filter_map(|row| {
+            let (uuid, stats) = row.ok()?;
+            Some((Uuid::parse_str(&uuid).ok()?, stats))
+        })
+        .collect();
+
+    Ok(stats)
+}
+
+pub fn save_rating(conn: &Connection, track: Uuid, stars: u8, favorite: bool) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO rating (track_uuid, stars, favorite) VALUES (?1, ?2, ?3)",
+        params![track.to_string(), stars, favorite],
+    )?;
+    Ok(())
+}
+
+/// Stars and favorite flag of every track that has been rated.
+pub fn ratings(conn: &Connection) -> Result<HashMap<Uuid, (u8, bool)>> {
+    let mut stmt = conn.prepare("SELECT track_uuid, stars, favorite FROM rating")?;
+
+    let ratings = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, u8>(1)?,
+                row.get::<_, bool>(2)?,
+            ))
+        })?
+        .filter_map(|row| {
+            let (uuid, stars, favorite) = row.ok()?;
+            Some((Uuid::parse_str(&uuid).ok()?, (stars, favorite)))
+        })
+        .collect();
+
+    Ok(ratings)
+}
+
+/// Every playlist with its tracks in order, sorted by name.
+pub fn playlists(conn: &Connection) -> Result<Vec<Playlist>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.name, t.track_uuid FROM playlist p
+        LEFT JOIN playlist_track t ON t.playlist_id = p.id
+        ORDER BY p.name, t.position",
+    )?;
+
+    let mut playlists: Vec<Playlist> = vec![];
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    })?;
+    for row in rows {
+        let (name, uuid) = row?;
+        if playlists.last().is_none_or(|p| p.name != name) {
+            playlists.push(Playlist {
+                name,
+                tracks: vec![],
+            });
+        }
+        if let Some(uuid) = uuid.and_then(|u| Uuid::parse_str(&u).ok()) {
+            playlists.last_mut().unwrap().tracks.push(uuid);
+        }
+    }
+
+    Ok(playlists)
+}
+
+/// Creates playlist or replaces tracks of an existing one.
+pub fn save_playlist(conn: &mut Connection, playlist: &Playlist) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT OR IGNORE INTO playlist (name) VALUES (?1)",
+        params![playlist.name],
+    )?;
+    let id: i64 = tx.query_row(
+        "SELECT id FROM playlist WHERE name = ?1",
+        params![playlist.name],
+        |row| row.get(0),
+    )?;
+
+    tx.execute(
+        "DELETE FROM playlist_track WHERE playlist_id = ?1",
+        params![id],
+    )?;
+    for (position, uuid) in playlist.tracks.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO playlist_track (playlist_id, position, track_uuid) VALUES (?1, ?2, ?3)",
+            params![id, position as i64, uuid.to_string()],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn delete_playlist(conn: &Connection, name: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM playlist_track WHERE playlist_id = (SELECT id FROM playlist WHERE name = ?1)",
+        params![name],
+    )?;
+    conn.execute("DELETE FROM playlist WHERE name = ?1", params![name])?;
+    Ok(())
+}
+
+/// Hides tracks from the library, files are left alone.
+pub fn remove_tracks(conn: &Connection, tracks: &[Uuid]) -> Result<()> {
+    for uuid in tracks {
+        conn.execute(
+            "INSERT OR IGNORE INTO removed_track (track_uuid) VALUES (?1)",
+            params![uuid.to_string()],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn removed_tracks(conn: &Connection) -> Result<HashSet<Uuid>> {
+    let mut stmt = conn.prepare("SELECT track_uuid FROM removed_track")?;
+    let removed = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .filter_map(|row| Uuid::parse_str(&row.ok()?).ok())
+        .collect();
+    Ok(removed)
+}
+
+fn to_unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn from_unix(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+fn join_uuids<'a>(uuids: impl IntoIterator<Item = &'a Uuid>) -> String {
+    uuids
+        .into_iter()
+        .map(Uuid::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_uuids<T: FromIterator<Uuid>>(s: &str) -> T {
+    s.split(',')
+        .filter_map(|u| Uuid::parse_str(u).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(repeat: Repeat) -> Session {
+        Session {
+            track: Some(Uuid::new_v4()),
+            pos: Duration::from_secs(42),
+            volume: 0.5,
+            shuffle: true,
+            manual_queue: VecDeque::from([Uuid::new_v4()]),
+            context: vec![Uuid::new_v4(), Uuid::new_v4()],
+            context_pos: 1,
+            repeat,
+        }
+    }
+
+    fn play(track: Uuid, started_at: i64, skipped: bool) -> Play {
+        Play {
+            track,
+            started_at: from_unix(started_at),
+            listened: Duration::from_secs(30),
+            skipped,
+            context: PlayContext::Queue,
+        }
+    }
+
+    #[test]
+    fn play_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        for play in [
+            play(a, 100, false),
+            play(a, 300, true),
+            play(a, 200, false),
+            play(b, 150, true),
+        ] {
+            record_play(&conn, &play).unwrap();
+        }
+
+        let stats = track_stats(&conn).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[&a].play_count, 2);
+        assert_eq!(stats[&a].skip_count, 1);
+        assert_eq!(stats[&a].last_played, Some(from_unix(300)));
+        assert_eq!(stats[&b].play_count, 0);
+        assert_eq!(stats[&b].skip_count, 1);
+
+        let recent = history(&conn, Some(from_unix(150))).unwrap();
+        let tracks = recent.iter().map(|p| p.track).collect::<Vec<_>>();
+        assert_eq!(tracks, [b, a, a]);
+        assert_eq!(
+            recent.iter().map(|p| p.skipped).collect::<Vec<_>>(),
+            [true, false, true]
+        );
+        assert!(recent.iter().all(|p| p.listened == Duration::from_secs(30)));
+        assert!(recent.iter().all(|p| p.context == PlayContext::Queue));
+        assert_eq!(history(&conn, None).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn session_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+
+        let saved = session(Repeat::One);
+        save_session(&conn, &saved).unwrap();
+        let loaded = load_session(&conn).unwrap().unwrap();
+        assert_eq!(loaded.track, saved.track);
+        assert_eq!(loaded.pos, saved.pos);
+        assert_eq!(loaded.manual_queue, saved.manual_queue);
+        assert_eq!(loaded.context, saved.context);
+        assert_eq!(loaded.context_pos, 1);
+        assert_eq!(loaded.repeat, Repeat::One);
+    }
+
+    #[test]
+    fn session_saved_before_repeat() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE session (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                track_uuid TEXT,
+                pos_ms INTEGER NOT NULL,
+                volume REAL NOT NULL,
+                shuffle INTEGER NOT NULL,
+                manual_queue TEXT NOT NULL,
+                context TEXT NOT NULL,
+                context_pos INTEGER NOT NULL
+            );
+            INSERT INTO session VALUES (1, NULL, 0, 1.0, 0, '', '', 0);",
+        )
+        .unwrap();
+
+        init(&conn).unwrap();
+        assert_eq!(load_session(&conn).unwrap().unwrap().repeat, Repeat::Off);
+
+        save_session(&conn, &session(Repeat::All)).unwrap();
+        assert_eq!(load_session(&conn).unwrap().unwrap().repeat, Repeat::All);
+    }
+}
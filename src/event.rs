@@ -1,9 +1,8 @@
-use std::{fs::File, path::PathBuf};
+use std::{fmt, fs::File, path::PathBuf, str::FromStr, time::Duration};
 
 use crossterm::event::{self, KeyCode, KeyModifiers};
 use rodio::Decoder;
-
-use crate::audio_thread::SinkState;
+use serde::Deserialize;
 
 #[derive(PartialEq, Debug)]
 pub enum EventState {
@@ -30,13 +29,25 @@ impl From<bool> for EventState {
 pub enum Event {
     Tick,
     Input(Key),
+    Mouse(event::MouseEvent),
+    Resize,
+    FocusGained,
+    FocusLost,
     Audio(AudioMessage),
+    /// Command read from stdin in headless mode.
+    Line(String),
+    /// Stdin was closed in headless mode, no more lines follow.
+    LinesEnd,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// Parsed from names written by [`Key`]'s `Display`, e.g. `Ctrl-p` or
+/// `PageUp`, so bindings can be overridden in the config file.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
 pub enum Key {
     Enter,
     Tab,
+    BackTab,
     Backspace,
     Esc,
 
@@ -79,6 +90,7 @@ impl From<event::KeyEvent> for Key {
         match code {
             KeyCode::Enter => Self::Enter,
             KeyCode::Tab => Self::Tab,
+            KeyCode::BackTab => Self::BackTab,
             KeyCode::Backspace => Self::Backspace,
             KeyCode::Esc => Self::Esc,
 
@@ -116,14 +128,144 @@ impl From<event::KeyEvent> for Key {
     }
 }
 
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Enter => write!(f, "Enter"),
+            Key::Tab => write!(f, "Tab"),
+            Key::BackTab => write!(f, "BackTab"),
+            Key::Backspace => write!(f, "Backspace"),
+            Key::Esc => write!(f, "Esc"),
+            Key::Left => write!(f, "Left"),
+            Key::Right => write!(f, "Right"),
+            Key::Up => write!(f, "Up"),
+            Key::Down => write!(f, "Down"),
+            Key::Insert => write!(f, "Insert"),
+            Key::Delete => write!(f, "Delete"),
+            Key::Home => write!(f, "Home"),
+            Key::End => write!(f, "End"),
+            Key::PageUp => write!(f, "PageUp"),
+            Key::PageDown => write!(f, "PageDown"),
+            Key::F0 => write!(f, "F0"),
+            Key::F1 => write!(f, "F1"),
+            Key::F2 => write!(f, "F2"),
+            Key::F3 => write!(f, "F3"),
+            Key::F4 => write!(f, "F4"),
+            Key::F5 => write!(f, "F5"),
+            Key::F6 => write!(f, "F6"),
+            Key::F7 => write!(f, "F7"),
+            Key::F8 => write!(f, "F8"),
+            Key::F9 => write!(f, "F9"),
+            Key::F10 => write!(f, "F10"),
+            Key::F11 => write!(f, "F11"),
+            Key::F12 => write!(f, "F12"),
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(c) => write!(f, "{c}"),
+            Key::Ctrl(c) => write!(f, "Ctrl-{c}"),
+            Key::Alt(c) => write!(f, "Alt-{c}"),
+            Key::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key::Char(c));
+        }
+
+        let with_char = |prefix: &str| {
+            let mut chars = s.get(prefix.len()..)?.chars();
+            let c = chars.next()?;
+            (s[..prefix.len()].eq_ignore_ascii_case(prefix) && chars.next().is_none()).then_some(c)
+        };
+        if let Some(c) = with_char("Ctrl-") {
+            return Ok(Key::Ctrl(c));
+        }
+        if let Some(c) = with_char("Alt-") {
+            return Ok(Key::Alt(c));
+        }
+
+        let key = match s.to_ascii_lowercase().as_str() {
+            "enter" => Key::Enter,
+            "tab" => Key::Tab,
+            "backtab" => Key::BackTab,
+            "backspace" => Key::Backspace,
+            "esc" => Key::Esc,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "insert" => Key::Insert,
+            "delete" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "space" => Key::Char(' '),
+            "f0" => Key::F0,
+            "f1" => Key::F1,
+            "f2" => Key::F2,
+            "f3" => Key::F3,
+            "f4" => Key::F4,
+            "f5" => Key::F5,
+            "f6" => Key::F6,
+            "f7" => Key::F7,
+            "f8" => Key::F8,
+            "f9" => Key::F9,
+            "f10" => Key::F10,
+            "f11" => Key::F11,
+            "f12" => Key::F12,
+            _ => return Err(format!("Unknown key \"{s}\"")),
+        };
+
+        Ok(key)
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 pub enum AudioMessage {
+    /// New track was loaded, possibly paused.
+    Started {
+        paused: bool,
+    },
+    Paused,
+    Resumed,
+    Position {
+        pos: Duration,
+        /// Time played of the current track, seeks and pauses excluded.
+        played: Duration,
+    },
+    VolumeChanged(f32),
+    DeviceChanged(Option<String>),
+    /// Output stopped receiving samples while playing.
+    Underrun,
+    Error(String),
     EndOfTrack,
-    State(SinkState),
-    Noop,
 }
 
 pub enum Command {
-    Play(Box<Decoder<File>>),
-    SendState,
-    Noop,
+    Play {
+        path: PathBuf,
+        source: Box<Decoder<File>>,
+        /// Where to start playback from.
+        pos: Duration,
+        paused: bool,
+    },
+    Pause,
+    Resume,
+    /// Seek to absolute position in the current track.
+    Seek(Duration),
+    SetVolume(f32),
+    SetDevice(String),
 }
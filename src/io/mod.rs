@@ -1,13 +1,25 @@
+use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use color_eyre::Result;
+use lofty::config::ParseOptions;
 use lofty::config::WriteOptions;
 use lofty::file::{AudioFile, TaggedFileExt};
+use lofty::id3::v2::{
+    Frame, PopularimeterFrame, SyncTextContentType, SynchronizedTextFrame, TimestampFormat,
+};
+use lofty::mpeg::MpegFile;
+use lofty::picture::PictureType;
 use lofty::read_from_path;
-use lofty::tag::{ItemKey, ItemValue, Tag, TagItem};
+use lofty::tag::{Accessor, ItemKey, ItemValue, Tag, TagItem, TagType};
+use rodio::{Decoder, Source};
 use uuid::Uuid;
 
-use crate::models::Track;
+use crate::lyrics::Lyrics;
+use crate::models::{MAX_RATING, Track, TrackStats, TrackTags};
+
+const POPM_EMAIL: &str = "mood";
 
 pub fn get_files(root: &Path, extension: &str) -> Result<Vec<PathBuf>> {
     let root = root.to_path_buf();
@@ -20,10 +32,10 @@ pub fn get_files(root: &Path, extension: &str) -> Result<Vec<PathBuf>> {
             let path = entry?.path();
             if path.is_dir() {
                 stack.push(path);
-            } else if let Some(ext) = path.extension() {
-                if ext == extension {
-                    files.push(path);
-                }
+            } else if let Some(ext) = path.extension()
+                && ext == extension
+            {
+                files.push(path);
             }
         }
     }
@@ -41,28 +53,34 @@ where
             let path = p.into();
 
             let mut tagged = read_from_path(&path).unwrap();
-            let duration = tagged.properties().duration();
+            let mut duration = tagged.properties().duration();
+            if duration.is_zero() {
+                duration = scan_duration(&path).unwrap_or_default();
+            }
+            let tags = read_tags(tagged.primary_tag().or(tagged.first_tag()));
 
             let tag = match tagged.primary_tag_mut() {
                 Some(tag) => {
                     if let Some(ItemValue::Text(uuid)) = tag
                         .get(&ItemKey::Unknown("MOOD_UUID".to_string()))
                         .map(|t| t.value())
+                        && let Ok(uuid) = Uuid::parse_str(uuid)
                     {
-                        if let Ok(uuid) = Uuid::parse_str(uuid) {
-                            return Track {
-                                uuid,
-                                duration,
-                                path,
-                            };
+                        return Track {
+                            uuid,
+                            duration,
+                            path,
+                            tags,
+                            stats: TrackStats::default(),
+                            rating: 0,
+                            favorite: false,
                         };
                     }
 
                     tag
                 }
                 None => {
-                    let tag =
-                        Tag::new(tagged.file_type().primary_tag_type());
+                    let tag = Tag::new(tagged.file_type().primary_tag_type());
                     tagged.insert_tag(tag);
                     tagged.primary_tag_mut().unwrap()
                 }
@@ -80,7 +98,191 @@ where
                 uuid,
                 duration,
                 path,
+                tags,
+                stats: TrackStats::default(),
+                rating: 0,
+                favorite: false,
             }
         })
         .collect()
 }
+
+/// Writes star rating into the file's primary tag, as POPM for ID3v2 and
+/// RATING (0-100) for everything else.
+pub fn write_rating(path: &Path, rating: u8) -> Result<()> {
+    let mut tagged = read_from_path(path)?;
+    let tag = match tagged.primary_tag_mut() {
+        Some(tag) => tag,
+        None => {
+            tagged.insert_tag(Tag::new(tagged.file_type().primary_tag_type()));
+            tagged.primary_tag_mut().unwrap()
+        }
+    };
+
+    if rating == 0 {
+        tag.remove_key(&ItemKey::Popularimeter);
+    } else {
+        let value = match tag.tag_type() {
+            TagType::Id3v2 => {
+                // Windows Media Player scale, most players read it.
+                let popm = [0, 1, 64, 128, 196, 255][rating.min(MAX_RATING) as usize];
+                let frame = PopularimeterFrame::new(POPM_EMAIL.to_string(), popm, 0);
+                ItemValue::Binary(frame.as_bytes()?)
+            }
+            _ => ItemValue::Text((u32::from(rating.min(MAX_RATING)) * 20).to_string()),
+        };
+        tag.insert_unchecked(TagItem::new(ItemKey::Popularimeter, value));
+    }
+
+    tagged.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+fn read_tags(tag: Option<&Tag>) -> TrackTags {
+    let Some(tag) = tag else {
+        return TrackTags::default();
+    };
+
+    TrackTags {
+        title: tag.title().map(String::from),
+        artist: tag.artist().map(String::from),
+        album: tag.album().map(String::from),
+        track_number: tag.track(),
+        year: tag.year(),
+        genre: tag.genre().map(String::from),
+    }
+}
+
+/// Replaces tags mood knows about in the file's primary tag, `None` fields
+/// are removed.
+pub fn write_tags(path: &Path, tags: &TrackTags) -> Result<()> {
+    let mut tagged = read_from_path(path)?;
+    let tag = match tagged.primary_tag_mut() {
+        Some(tag) => tag,
+        None => {
+            tagged.insert_tag(Tag::new(tagged.file_type().primary_tag_type()));
+            tagged.primary_tag_mut().unwrap()
+        }
+    };
+
+    match tags.title.clone() {
+        Some(title) => tag.set_title(title),
+        None => tag.remove_title(),
+    }
+    match tags.artist.clone() {
+        Some(artist) => tag.set_artist(artist),
+        None => tag.remove_artist(),
+    }
+    match tags.album.clone() {
+        Some(album) => tag.set_album(album),
+        None => tag.remove_album(),
+    }
+    match tags.track_number {
+        Some(track) => tag.set_track(track),
+        None => tag.remove_track(),
+    }
+    match tags.year {
+        Some(year) => tag.set_year(year),
+        None => tag.remove_year(),
+    }
+    match tags.genre.clone() {
+        Some(genre) => tag.set_genre(genre),
+        None => tag.remove_genre(),
+    }
+
+    tagged.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+/// Folder images used as cover when a track has no embedded picture.
+const FOLDER_COVERS: [&str; 6] = [
+    "cover.jpg",
+    "cover.png",
+    "Cover.jpg",
+    "folder.jpg",
+    "Folder.jpg",
+    "front.jpg",
+];
+
+/// Encoded cover image of a track, front cover or the first embedded picture,
+/// falling back to a cover image in the track's folder.
+pub fn read_cover(path: &Path) -> Option<Vec<u8>> {
+    if let Ok(tagged) = read_from_path(path) {
+        let pictures = tagged.tags().iter().flat_map(|t| t.pictures());
+        let picture = pictures
+            .clone()
+            .find(|p| p.pic_type() == PictureType::CoverFront)
+            .or_else(|| pictures.clone().next());
+        if let Some(picture) = picture {
+            return Some(picture.data().to_vec());
+        }
+    }
+
+    let dir = path.parent()?;
+    FOLDER_COVERS
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+        .and_then(|p| std::fs::read(p).ok())
+}
+
+/// Lyrics from a sidecar `.lrc` file next to the track, embedded SYLT or
+/// embedded unsynced lyrics, whichever is found first.
+pub fn read_lyrics(path: &Path) -> Option<Lyrics> {
+    if let Ok(lrc) = std::fs::read_to_string(path.with_extension("lrc")) {
+        let lyrics = Lyrics::parse_lrc(&lrc);
+        if !lyrics.is_empty() {
+            return Some(lyrics);
+        }
+    }
+
+    if let Some(lyrics) = read_synced_lyrics(path) {
+        return Some(lyrics);
+    }
+
+    let tagged = read_from_path(path).ok()?;
+    let text = tagged
+        .tags()
+        .iter()
+        .find_map(|t| t.get_string(&ItemKey::Lyrics))?;
+    // Some taggers put LRC into USLT.
+    Some(Lyrics::parse_lrc(text)).filter(|l| !l.is_empty())
+}
+
+/// SYLT is only kept as a raw frame by lofty, so it's read from the ID3v2
+/// tag directly. Timestamps in MPEG frames aren't supported.
+fn read_synced_lyrics(path: &Path) -> Option<Lyrics> {
+    let mut file = File::open(path).ok()?;
+    let mpeg = MpegFile::read_from(&mut file, ParseOptions::new()).ok()?;
+
+    mpeg.id3v2()?.into_iter().find_map(|frame| {
+        let Frame::Binary(binary) = frame else {
+            return None;
+        };
+        if frame.id_str() != "SYLT" {
+            return None;
+        }
+
+        let sylt = SynchronizedTextFrame::parse(&binary.data, frame.flags()).ok()?;
+        (sylt.timestamp_format == TimestampFormat::MS
+            && sylt.content_type == SyncTextContentType::Lyrics)
+            .then(|| Lyrics::from_synced(sylt.content))
+    })
+}
+
+/// Finds duration of files whose headers don't tell it by decoding them
+/// whole.
+pub fn scan_duration(path: &Path) -> Result<Duration> {
+    let decoder = Decoder::new(File::open(path)?)?;
+    if let Some(duration) = decoder.total_duration() {
+        return Ok(duration);
+    }
+
+    let per_sec = decoder.sample_rate() as f64 * decoder.channels() as f64;
+    if per_sec == 0.0 {
+        return Ok(Duration::ZERO);
+    }
+    let samples = decoder.count();
+
+    Ok(Duration::from_secs_f64(samples as f64 / per_sec))
+}
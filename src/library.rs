@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use color_eyre::Result;
+use rusqlite::Connection;
+
+use crate::db;
+use crate::io::{add_metadata, get_files};
+use crate::models::Track;
+
+/// Tracks under `audio_dir` except ones removed from the library, with
+/// play counts and ratings from the database. Files seen for the first
+/// time get an id written into their tags.
+pub fn load(audio_dir: &Path, sqlite: &Connection) -> Result<Vec<Track>> {
+    let paths = get_files(audio_dir, "mp3")?;
+    let mut tracks = add_metadata(paths);
+
+    let removed = db::removed_tracks(sqlite)?;
+    tracks.retain(|t| !removed.contains(&t.uuid));
+
+    let stats = db::track_stats(sqlite)?;
+    let ratings = db::ratings(sqlite)?;
+    for track in tracks.iter_mut() {
+        if let Some(stats) = stats.get(&track.uuid) {
+            track.stats = stats.clone();
+        }
+        if let Some(&(rating, favorite)) = ratings.get(&track.uuid) {
+            track.rating = rating;
+            track.favorite = favorite;
+        }
+    }
+
+    Ok(tracks)
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+/// Lyrics of a track, synced when lines have timestamps.
+#[derive(Debug, Clone, Default)]
+pub struct Lyrics {
+    pub lines: Vec<LyricLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LyricLine {
+    pub time: Option<Duration>,
+    pub text: String,
+}
+
+impl Lyrics {
+    /// Parses LRC, plain text comes out as unsynced lyrics.
+    ///
+    /// Lines can have several timestamps (`[00:12.00][01:30.50]Chorus`) and
+    /// are repeated for each of them. `[offset:ms]` shifts every timestamp,
+    /// positive offset makes lines show up sooner.
+    pub fn parse_lrc(s: &str) -> Self {
+        let mut offset = 0i64;
+        let mut synced = vec![];
+        let mut plain = vec![];
+
+        for line in s.lines() {
+            let mut rest = line.trim();
+            let mut times = vec![];
+            let mut tagged = false;
+
+            while let Some(tag) = rest.strip_prefix('[')
+                && let Some((tag, after)) = tag.split_once(']')
+            {
+                if let Some(time) = parse_timestamp(tag) {
+                    times.push(time);
+                } else if let Some(ms) = tag.strip_prefix("offset:") {
+                    offset = ms.trim().parse().unwrap_or(0);
+                } else if !tag.contains(':') {
+                    // Not a tag, e.g. `[Chorus]` in plain lyrics.
+                    break;
+                }
+                tagged = true;
+                rest = after;
+            }
+
+            let text = rest.trim().to_string();
+            if !times.is_empty() {
+                synced.extend(times.into_iter().map(|t| (t, text.clone())));
+            } else if !tagged {
+                plain.push(LyricLine { time: None, text });
+            }
+        }
+
+        if synced.is_empty() {
+            return Lyrics { lines: plain };
+        }
+
+        let mut lines = synced
+            .into_iter()
+            .map(|(time, text)| LyricLine {
+                time: Some(shift(time, offset.saturating_neg())),
+                text,
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|l| l.time);
+
+        Lyrics { lines }
+    }
+
+    /// Lyrics from timestamped lines, `(milliseconds, text)`.
+    pub fn from_synced(lines: impl IntoIterator<Item = (u32, String)>) -> Self {
+        let mut lines = lines
+            .into_iter()
+            .map(|(ms, text)| LyricLine {
+                time: Some(Duration::from_millis(ms as u64)),
+                text: text.trim_end_matches(['\n', '\r']).to_string(),
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|l| l.time);
+
+        Lyrics { lines }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.lines.iter().any(|l| l.time.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Index of the line being sung at `pos`.
+    pub fn current(&self, pos: Duration) -> Option<usize> {
+        if !self.is_synced() {
+            return None;
+        }
+
+        self.lines
+            .iter()
+            .rposition(|l| l.time.is_some_and(|t| t <= pos))
+    }
+}
+
+/// Parses `mm:ss`, `mm:ss.xx` or `mm:ss:xx`.
+fn parse_timestamp(s: &str) -> Option<Duration> {
+    let (mins, rest) = s.split_once(':')?;
+    let mins: u64 = mins.trim().parse().ok()?;
+
+    let (secs, frac) = match rest.split_once(['.', ':']) {
+        Some((secs, frac)) => (secs, frac),
+        None => (rest, "0"),
+    };
+    let secs: u64 = secs.parse().ok()?;
+    if !frac.chars().all(|c| c.is_ascii_digit()) || frac.is_empty() {
+        return None;
+    }
+    // `.5` is half a second, `.05` and `.050` are 50 milliseconds.
+    let millis = format!("{frac:0<3}")[..3].parse::<u64>().ok()?;
+
+    let secs = mins.checked_mul(60)?.checked_add(secs)?;
+    Some(Duration::from_millis(
+        secs.checked_mul(1000)?.checked_add(millis)?,
+    ))
+}
+
+fn shift(time: Duration, ms: i64) -> Duration {
+    if ms >= 0 {
+        time.saturating_add(Duration::from_millis(ms as u64))
+    } else {
+        time.saturating_sub(Duration::from_millis(ms.unsigned_abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(lyrics: &Lyrics) -> Vec<Option<u128>> {
+        lyrics
+            .lines
+            .iter()
+            .map(|l| l.time.map(|t| t.as_millis()))
+            .collect()
+    }
+
+    fn texts(lyrics: &Lyrics) -> Vec<&str> {
+        lyrics.lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn timestamp_formats() {
+        let lyrics =
+            Lyrics::parse_lrc("[01:02]a\n[01:02.5]b\n[01:02.05]c\n[01:02:050]d\n[1:02.123]e");
+        assert_eq!(
+            times(&lyrics),
+            [
+                Some(62_000),
+                Some(62_050),
+                Some(62_050),
+                Some(62_123),
+                Some(62_500)
+            ]
+        );
+    }
+
+    #[test]
+    fn several_timestamps_per_line() {
+        let lyrics = Lyrics::parse_lrc("[00:30.00][00:10.00]Chorus\n[00:20.00] Verse ");
+        assert_eq!(times(&lyrics), [Some(10_000), Some(20_000), Some(30_000)]);
+        assert_eq!(texts(&lyrics), ["Chorus", "Verse", "Chorus"]);
+    }
+
+    #[test]
+    fn sorted_by_time() {
+        let lyrics = Lyrics::parse_lrc("[00:03.00]c\n[00:01.00]a\n[00:02.00]b");
+        assert_eq!(texts(&lyrics), ["a", "b", "c"]);
+        assert_eq!(lyrics.current(Duration::from_millis(2500)), Some(1));
+        assert_eq!(lyrics.current(Duration::from_millis(500)), None);
+    }
+
+    #[test]
+    fn offset() {
+        let sooner = Lyrics::parse_lrc("[offset:500]\n[00:10.00]a\n[00:00.20]b");
+        assert_eq!(times(&sooner), [Some(0), Some(9_500)]);
+
+        let later = Lyrics::parse_lrc("[offset: -250]\n[00:10.00]a");
+        assert_eq!(times(&later), [Some(10_250)]);
+
+        // Saturates instead of overflowing.
+        let extreme = Lyrics::parse_lrc(&format!("[offset:{}]\n[00:10.00]a", i64::MIN));
+        assert_eq!(times(&extreme), [Some(i64::MAX as u128 + 10_000)]);
+    }
+
+    #[test]
+    fn metadata_and_malformed_timestamps() {
+        let lrc = "[ar:Artist]\n[ti:Title]\n[00:01.00]a\n[aa:10.00]b\n[00:1x.00]c\n\
+                   [00:10.]d\n[00:10.ab]e\n[300000000000000000:00.00]f\n[00:02.00]g";
+        let lyrics = Lyrics::parse_lrc(lrc);
+        assert!(lyrics.is_synced());
+        assert_eq!(texts(&lyrics), ["a", "g"]);
+    }
+
+    #[test]
+    fn plain_text() {
+        let lyrics = Lyrics::parse_lrc("[Chorus]\nla la\n\n[ar:Artist]\nla");
+        assert!(!lyrics.is_synced());
+        assert_eq!(times(&lyrics), [None, None, None, None]);
+        assert_eq!(texts(&lyrics), ["[Chorus]", "la la", "", "la"]);
+        assert_eq!(lyrics.current(Duration::ZERO), None);
+    }
+}
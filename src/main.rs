@@ -1,64 +1,128 @@
 use std::path::PathBuf;
-use std::time::Duration;
 
+use clap::Parser;
+use crossterm::cursor::{MoveTo, RestorePosition, SavePosition};
+use crossterm::event::{
+    DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+};
+use crossterm::style::Print;
+use ratatui::DefaultTerminal;
 use rusqlite::Connection;
 
 use crate::app::App;
 use crate::audio_thread::AudioThread;
+use crate::cli::Cli;
 use crate::config::Config;
 use crate::event::Event;
-use crate::utils::spawn_event_emmiter;
+use crate::utils::{TickRate, spawn_event_emmiter};
 
 mod app;
 mod audio_thread;
+mod backend;
+mod cli;
+mod commands;
 mod components;
 mod config;
 mod current_track;
+mod db;
 mod event;
 mod io;
+mod library;
+mod lyrics;
 mod models;
+mod playlist_file;
+mod queue;
 mod source;
+mod stats;
 mod utils;
 
 fn main() -> color_eyre::Result<()> {
-    let mut terminal = ratatui::init();
+    let cli = Cli::parse();
+    let config = Config::load(PathBuf::from("/home/lf/music"))?;
+    let sqlite = db::open()?;
+
+    if cli.headless || cli.command.is_some() {
+        return cli::run(cli, config, sqlite);
+    }
+    run_tui(config, sqlite)
+}
 
-    let tickrate = Duration::from_millis(250);
+fn run_tui(config: Config, sqlite: Connection) -> color_eyre::Result<()> {
+    let mut terminal = ratatui::init();
+    crossterm::execute!(std::io::stdout(), EnableMouseCapture, EnableFocusChange)?;
 
     let (event_tx, event_rx) = crossbeam_channel::unbounded();
     let (command_tx, command_rx) = crossbeam_channel::unbounded();
 
-    spawn_event_emmiter(event_tx.clone(), tickrate)?;
-    AudioThread::new(command_rx, event_tx).run()?;
-
-    let config = Config::new(PathBuf::from("/home/lf/music"));
-
-    let sqlite = Connection::open("db.db3")?;
+    let tick_rate = TickRate::new(config.tick_rate);
+    spawn_event_emmiter(event_tx.clone(), tick_rate.clone())?;
+    AudioThread::new(command_rx, event_tx, &config).run()?;
 
     let mut app = App::new(command_tx, config, sqlite)?;
 
-    terminal.draw(|f| app.render(f.area(), f.buffer_mut()))?;
+    draw(&mut terminal, &app)?;
     loop {
         match event_rx.recv()? {
             Event::Input(key) => {
-                if !app.event(key)?.is_consumed() {
-                    if key == app.config.key_config.quit {
-                        break;
-                    }
+                if !app.event(key)?.is_consumed() && key == app.config.key_config.quit {
+                    break;
+                }
+            }
+            Event::Mouse(mouse) => {
+                app.mouse(mouse)?;
+            }
+            // Redrawn below, components recompute scroll offsets for the new
+            // size while rendering.
+            Event::Resize => app.resize(),
+            Event::FocusLost => {
+                if let Some(unfocused) = app.config.unfocused_tick_rate {
+                    tick_rate.set(unfocused);
                 }
             }
+            Event::FocusGained => {
+                tick_rate.set(app.config.tick_rate);
+            }
             Event::Tick => {
                 app.tick()?;
             }
             Event::Audio(audio) => {
-                app.audio(audio);
+                if !app.audio(audio)? {
+                    continue;
+                }
             }
+            // Only sent in headless mode.
+            Event::Line(_) | Event::LinesEnd => {}
         }
 
-        terminal.draw(|f| app.render(f.area(), f.buffer_mut()))?;
+        if app.should_quit() {
+            break;
+        }
+        draw(&mut terminal, &app)?;
     }
 
+    let saved = app
+        .save_session()
+        .and_then(|()| app.record_unfinished_play());
+
+    _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture, DisableFocusChange);
     ratatui::restore();
 
+    saved
+}
+
+fn draw(terminal: &mut DefaultTerminal, app: &App) -> color_eyre::Result<()> {
+    terminal.draw(|f| app.render(f.area(), f.buffer_mut()))?;
+
+    // Images drawn with terminal graphics protocols bypass ratatui's buffer.
+    if let Some((area, seq)) = app.graphics() {
+        crossterm::execute!(
+            std::io::stdout(),
+            SavePosition,
+            MoveTo(area.x, area.y),
+            Print(seq),
+            RestorePosition
+        )?;
+    }
+
     Ok(())
 }
@@ -1,3 +1,5 @@
+mod playlist;
 mod track;
 
-pub use track::Track;
+pub use playlist::Playlist;
+pub use track::{MAX_RATING, PlayContext, Track, TrackStats, TrackTags};
@@ -0,0 +1,7 @@
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    pub name: String,
+    pub tracks: Vec<Uuid>,
+}
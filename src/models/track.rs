@@ -1,4 +1,7 @@
-use std::{path::PathBuf, time::Duration};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
 use uuid::Uuid;
 
@@ -7,4 +10,68 @@ pub struct Track {
     pub uuid: Uuid,
     pub duration: Duration,
     pub path: PathBuf,
+    pub tags: TrackTags,
+    pub stats: TrackStats,
+    /// Stars from 0 to [`MAX_RATING`], 0 is unrated.
+    pub rating: u8,
+    pub favorite: bool,
+}
+
+pub const MAX_RATING: u8 = 5;
+
+impl Track {
+    /// Title from tags, file name if there is none.
+    pub fn name(&self) -> String {
+        match self.tags.title.as_ref() {
+            Some(title) => title.clone(),
+            None => self.path.file_stem().unwrap().to_string_lossy().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+    pub year: Option<u32>,
+    pub genre: Option<String>,
+}
+
+/// Derived from play history.
+#[derive(Debug, Clone, Default)]
+pub struct TrackStats {
+    /// Plays that weren't skipped.
+    pub play_count: u32,
+    pub skip_count: u32,
+    pub last_played: Option<SystemTime>,
+}
+
+/// Where playback of a track was started from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayContext {
+    Library,
+    Queue,
+}
+
+impl FromStr for PlayContext {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "library" => Ok(PlayContext::Library),
+            "queue" => Ok(PlayContext::Queue),
+            _ => Err(format!("Unknown play context \"{s}\"")),
+        }
+    }
+}
+
+impl fmt::Display for PlayContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayContext::Library => write!(f, "library"),
+            PlayContext::Queue => write!(f, "queue"),
+        }
+    }
 }
@@ -0,0 +1,575 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use uuid::Uuid;
+
+use crate::models::Track;
+use crate::utils::canonical;
+
+/// Characters escaped in `file://` locations, `/` is kept.
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Durations further apart than this don't belong to the same recording.
+const DURATION_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// Playlist file formats other players and DJ software understand.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlaylistFormat {
+    /// Extended M3U, `.m3u` and `.m3u8` are both written as UTF-8.
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    pub const EXTENSIONS: [&str; 4] = ["m3u", "m3u8", "pls", "xspf"];
+
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "m3u" | "m3u8" => Ok(PlaylistFormat::M3u),
+            "pls" => Ok(PlaylistFormat::Pls),
+            "xspf" => Ok(PlaylistFormat::Xspf),
+            _ => Err(eyre!(
+                "Unknown playlist format \"{}\", expected one of {}",
+                path.display(),
+                Self::EXTENSIONS.join(", ")
+            )),
+        }
+    }
+}
+
+/// Track as listed in a playlist file, before it's looked up in the
+/// library.
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    /// Path or URI as written in the file.
+    pub location: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+/// Playlist read from a file and matched against the library.
+pub struct Import {
+    /// Title stored in the file, file name without extension otherwise.
+    pub name: String,
+    pub tracks: Vec<Uuid>,
+    /// Entries whose file is gone and which no library track matches.
+    pub unresolved: Vec<String>,
+    /// Entries whose file is gone but matched a library track by tags.
+    pub moved: usize,
+}
+
+/// Reads playlist at `path`. Relative locations are looked up next to the
+/// playlist first, then under each of `roots`.
+pub fn import(path: &Path, roots: &[PathBuf], library: &[Track]) -> Result<Import> {
+    let format = PlaylistFormat::from_path(path)?;
+    let text = decode(&std::fs::read(path)?);
+    let (title, entries) = parse(format, &text)?;
+
+    let name = title.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    let base = path.parent().unwrap_or(Path::new("."));
+    let by_path = library
+        .iter()
+        .map(|t| (canonical(&t.path), t.uuid))
+        .collect::<HashMap<_, _>>();
+
+    let mut import = Import {
+        name,
+        tracks: vec![],
+        unresolved: vec![],
+        moved: 0,
+    };
+    for entry in entries {
+        let found = candidates(&entry.location, base, roots)
+            .into_iter()
+            .find_map(|p| by_path.get(&canonical(&p)).copied());
+        if let Some(uuid) = found {
+            import.tracks.push(uuid);
+        } else if let Some(uuid) = match_moved(&entry, library) {
+            import.tracks.push(uuid);
+            import.moved += 1;
+        } else {
+            import.unresolved.push(entry.location);
+        }
+    }
+
+    Ok(import)
+}
+
+/// Writes `tracks` to `path`, relative to where the file is.
+pub fn export(path: &Path, format: PlaylistFormat, name: &str, tracks: &[&Track]) -> Result<()> {
+    let base = std::path::absolute(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    std::fs::write(path, write(format, name, tracks, Some(&base)))?;
+    Ok(())
+}
+
+/// Playlist title if the file has one, and its entries in order.
+pub fn parse(format: PlaylistFormat, text: &str) -> Result<(Option<String>, Vec<Entry>)> {
+    match format {
+        PlaylistFormat::M3u => Ok(parse_m3u(text)),
+        PlaylistFormat::Pls => Ok((None, parse_pls(text))),
+        PlaylistFormat::Xspf => parse_xspf(text),
+    }
+}
+
+/// Serializes `tracks`, paths under `base` are written relative to it
+/// except in XSPF, which gets absolute `file://` URIs.
+pub fn write(format: PlaylistFormat, name: &str, tracks: &[&Track], base: Option<&Path>) -> String {
+    let mut out = String::new();
+    let location = |track: &Track| {
+        let path = std::path::absolute(&track.path).unwrap_or_else(|_| track.path.clone());
+        match base.and_then(|b| path.strip_prefix(b).ok()) {
+            Some(relative) => relative.to_string_lossy().to_string(),
+            None => path.to_string_lossy().to_string(),
+        }
+    };
+
+    match format {
+        PlaylistFormat::M3u => {
+            _ = writeln!(out, "#EXTM3U");
+            _ = writeln!(out, "#PLAYLIST:{name}");
+            for track in tracks {
+                _ = writeln!(out, "#EXTINF:{},{}", seconds(track), display_name(track));
+                _ = writeln!(out, "{}", location(track));
+            }
+        }
+        PlaylistFormat::Pls => {
+            _ = writeln!(out, "[playlist]");
+            for (i, track) in tracks.iter().enumerate() {
+                let n = i + 1;
+                _ = writeln!(out, "File{n}={}", location(track));
+                _ = writeln!(out, "Title{n}={}", display_name(track));
+                _ = writeln!(out, "Length{n}={}", seconds(track));
+            }
+            _ = writeln!(out, "NumberOfEntries={}", tracks.len());
+            _ = writeln!(out, "Version=2");
+        }
+        PlaylistFormat::Xspf => {
+            _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+            _ = writeln!(
+                out,
+                r#"<playlist version="1" xmlns="http://xspf.org/ns/0/">"#
+            );
+            _ = writeln!(out, "  <title>{}</title>", escape(name));
+            _ = writeln!(out, "  <trackList>");
+            for track in tracks {
+                let path = std::path::absolute(&track.path).unwrap_or_else(|_| track.path.clone());
+                let uri = utf8_percent_encode(&path.to_string_lossy(), PATH).to_string();
+                _ = writeln!(out, "    <track>");
+                _ = writeln!(out, "      <location>file://{}</location>", escape(&uri));
+                _ = writeln!(out, "      <title>{}</title>", escape(&track.name()));
+                if let Some(artist) = track.tags.artist.as_deref() {
+                    _ = writeln!(out, "      <creator>{}</creator>", escape(artist));
+                }
+                if let Some(album) = track.tags.album.as_deref() {
+                    _ = writeln!(out, "      <album>{}</album>", escape(album));
+                }
+                if !track.duration.is_zero() {
+                    _ = writeln!(
+                        out,
+                        "      <duration>{}</duration>",
+                        track.duration.as_millis()
+                    );
+                }
+                _ = writeln!(out, "    </track>");
+            }
+            _ = writeln!(out, "  </trackList>");
+            _ = writeln!(out, "</playlist>");
+        }
+    }
+
+    out
+}
+
+/// `#EXTINF:<seconds>[ attributes],<artist> - <title>` describes the
+/// location on the next line, other comments are skipped.
+fn parse_m3u(text: &str) -> (Option<String>, Vec<Entry>) {
+    let mut title = None;
+    let mut entries = vec![];
+    let mut info = Entry::default();
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (length, display) = extinf.split_once(',').unwrap_or((extinf, ""));
+            let length = length.split_whitespace().next().unwrap_or_default();
+            info.duration = parse_seconds(length);
+            (info.artist, info.title) = split_display(display);
+        } else if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            title = Some(name.trim().to_string());
+        } else if !line.starts_with('#') {
+            info.location = line.to_string();
+            entries.push(std::mem::take(&mut info));
+        }
+    }
+
+    (title, entries)
+}
+
+/// INI style `FileN`, `TitleN` and `LengthN` keys, entries are ordered by
+/// `N` rather than by where they are in the file.
+fn parse_pls(text: &str) -> Vec<Entry> {
+    let mut entries = BTreeMap::<u32, Entry>::new();
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        let split = key.find(|c: char| c.is_ascii_digit()).unwrap_or(key.len());
+        let (field, n) = key.split_at(split);
+        let Ok(n) = n.parse() else {
+            continue;
+        };
+
+        let entry = entries.entry(n).or_default();
+        match field {
+            "file" => entry.location = value.to_string(),
+            "title" => (entry.artist, entry.title) = split_display(value),
+            "length" => entry.duration = parse_seconds(value),
+            _ => {}
+        }
+    }
+
+    entries
+        .into_values()
+        .filter(|e| !e.location.is_empty())
+        .collect()
+}
+
+fn parse_xspf(text: &str) -> Result<(Option<String>, Vec<Entry>)> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    let child = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|n| n.tag_name().name() == name)
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    let entries = root
+        .children()
+        .filter(|n| n.tag_name().name() == "trackList")
+        .flat_map(|list| list.children().filter(|n| n.tag_name().name() == "track"))
+        .filter_map(|track| {
+            Some(Entry {
+                location: child(track, "location")?,
+                title: child(track, "title"),
+                artist: child(track, "creator"),
+                duration: child(track, "duration")
+                    .and_then(|ms| ms.parse().ok())
+                    .map(Duration::from_millis),
+            })
+        })
+        .collect();
+
+    Ok((child(root, "title"), entries))
+}
+
+/// Paths `location` could refer to, most likely first.
+fn candidates(location: &str, base: &Path, roots: &[PathBuf]) -> Vec<PathBuf> {
+    let location = match location.strip_prefix("file://") {
+        // `file:///music/a.mp3` and `file://localhost/music/a.mp3`.
+        Some(uri) => {
+            let path = uri.strip_prefix("localhost").unwrap_or(uri);
+            percent_decode_str(path).decode_utf8_lossy().to_string()
+        }
+        None if location.contains("://") => return vec![],
+        None => location.to_string(),
+    };
+
+    // Playlists made on Windows, unless the file name really has one.
+    let mut spellings = vec![PathBuf::from(&location)];
+    if location.contains('\\') {
+        spellings.push(PathBuf::from(location.replace('\\', "/")));
+    }
+
+    spellings
+        .into_iter()
+        .flat_map(|path| {
+            if path.is_absolute() {
+                vec![path]
+            } else {
+                std::iter::once(base)
+                    .chain(roots.iter().map(PathBuf::as_path))
+                    .map(|dir| dir.join(&path))
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+/// Library track with the title, artist and duration the entry lists,
+/// checking only what both sides know. Entries without a title match by
+/// file name. Ambiguous matches are left unresolved.
+fn match_moved(entry: &Entry, library: &[Track]) -> Option<Uuid> {
+    let same = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+    let file_name = entry
+        .location
+        .rsplit(['/', '\\'])
+        .next()
+        .map(|n| percent_decode_str(n).decode_utf8_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut matches = library.iter().filter(|track| match entry.title.as_deref() {
+        Some(title) => {
+            same(title, &track.name())
+                && entry
+                    .artist
+                    .as_deref()
+                    .zip(track.tags.artist.as_deref())
+                    .is_none_or(|(a, b)| same(a, b))
+                && entry
+                    .duration
+                    .filter(|_| !track.duration.is_zero())
+                    .is_none_or(|d| d.abs_diff(track.duration) <= DURATION_TOLERANCE)
+        }
+        None => track
+            .path
+            .file_name()
+            .is_some_and(|n| !file_name.is_empty() && same(&n.to_string_lossy(), &file_name)),
+    });
+
+    match (matches.next(), matches.next()) {
+        (Some(track), None) => Some(track.uuid),
+        _ => None,
+    }
+}
+
+/// Playlists without a BOM that aren't valid UTF-8 are taken to be
+/// Latin-1, which plain `.m3u` files usually are.
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// `-1` means unknown.
+fn parse_seconds(s: &str) -> Option<Duration> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| *secs > 0.0)
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+fn seconds(track: &Track) -> i64 {
+    if track.duration.is_zero() {
+        -1
+    } else {
+        track.duration.as_secs() as i64
+    }
+}
+
+/// `Artist - Title` as most players write it.
+fn display_name(track: &Track) -> String {
+    match track.tags.artist.as_deref() {
+        Some(artist) => format!("{artist} - {}", track.name()),
+        None => track.name(),
+    }
+}
+
+fn split_display(display: &str) -> (Option<String>, Option<String>) {
+    let display = display.trim();
+    match display.split_once(" - ") {
+        Some((artist, title)) => (Some(artist.to_string()), Some(title.to_string())),
+        None if display.is_empty() => (None, None),
+        None => (None, Some(display.to_string())),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{TrackStats, TrackTags};
+
+    fn track(path: &str, artist: Option<&str>, title: &str, secs: u64) -> Track {
+        Track {
+            uuid: Uuid::new_v4(),
+            duration: Duration::from_secs(secs),
+            path: PathBuf::from(path),
+            tags: TrackTags {
+                title: Some(title.to_string()),
+                artist: artist.map(str::to_string),
+                ..Default::default()
+            },
+            stats: TrackStats::default(),
+            rating: 0,
+            favorite: false,
+        }
+    }
+
+    fn entry(location: &str, artist: Option<&str>, title: Option<&str>, secs: u64) -> Entry {
+        Entry {
+            location: location.to_string(),
+            title: title.map(str::to_string),
+            artist: artist.map(str::to_string),
+            duration: Some(Duration::from_secs(secs)),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let library = [
+            track("/music/a b.mp3", Some("Nina & Co"), "Fast <Remix>", 200),
+            track("/music/sub/c#1.flac", None, "Slow", 61),
+        ];
+        let tracks = library.iter().collect::<Vec<_>>();
+        let base = Path::new("/music");
+
+        for format in [
+            PlaylistFormat::M3u,
+            PlaylistFormat::Pls,
+            PlaylistFormat::Xspf,
+        ] {
+            let text = write(format, "Mix", &tracks, Some(base));
+            let (title, entries) = parse(format, &text).unwrap();
+
+            let expected_title = (format != PlaylistFormat::Pls).then(|| "Mix".to_string());
+            assert_eq!(title, expected_title, "{format:?}");
+            assert_eq!(entries.len(), library.len(), "{format:?}");
+            for (entry, track) in entries.iter().zip(&library) {
+                let found = candidates(&entry.location, base, &[]);
+                assert_eq!(found.first(), Some(&track.path), "{format:?}");
+                assert_eq!(entry.title, track.tags.title, "{format:?}");
+                assert_eq!(entry.artist, track.tags.artist, "{format:?}");
+                assert_eq!(entry.duration, Some(track.duration), "{format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn relative_locations() {
+        let track = track("/music/sub/a.mp3", None, "A", 1);
+        let text = write(
+            PlaylistFormat::M3u,
+            "Mix",
+            &[&track],
+            Some(Path::new("/music")),
+        );
+        assert!(text.lines().any(|l| l == "sub/a.mp3"));
+    }
+
+    #[test]
+    fn windows_paths() {
+        let roots = [PathBuf::from("/music")];
+        let found = candidates(r"Artist\Album\01.mp3", Path::new("/playlists"), &roots);
+        assert!(found.contains(&PathBuf::from("/playlists/Artist/Album/01.mp3")));
+        assert!(found.contains(&PathBuf::from("/music/Artist/Album/01.mp3")));
+
+        let library = [track("/music/x/01.mp3", None, "One", 1)];
+        let moved = entry(r"C:\Music\01.mp3", None, None, 1);
+        assert_eq!(match_moved(&moved, &library), Some(library[0].uuid));
+    }
+
+    #[test]
+    fn file_uris() {
+        let base = Path::new("/playlists");
+        let expected = vec![PathBuf::from("/music/a b.mp3")];
+        assert_eq!(candidates("file:///music/a%20b.mp3", base, &[]), expected);
+        assert_eq!(
+            candidates("file://localhost/music/a%20b.mp3", base, &[]),
+            expected
+        );
+        assert!(candidates("http://example.com/a.mp3", base, &[]).is_empty());
+    }
+
+    #[test]
+    fn latin1_m3u() {
+        let bytes = b"#EXTM3U\n#EXTINF:10,Bj\xf6rk - J\xf3ga\nj\xf3ga.mp3\n";
+        let (_, entries) = parse_m3u(&decode(bytes));
+        assert_eq!(entries[0].artist.as_deref(), Some("Björk"));
+        assert_eq!(entries[0].title.as_deref(), Some("Jóga"));
+        assert_eq!(entries[0].location, "jóga.mp3");
+
+        let bom = "\u{FEFF}#EXTM3U\nä.mp3\n";
+        assert_eq!(decode(bom.as_bytes()), "#EXTM3U\nä.mp3\n");
+    }
+
+    #[test]
+    fn unrepresentable_lengths() {
+        let text = "#EXTM3U\n#EXTINF:1e400,A\na.mp3\n#EXTINF:1e30,B\nb.mp3\n#EXTINF:NaN,C\nc.mp3\n";
+        let (_, entries) = parse_m3u(text);
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|e| e.duration.is_none()));
+
+        let text =
+            "[playlist]\nFile1=a.mp3\nLength1=inf\nFile2=b.mp3\nLength2=-1\nNumberOfEntries=2\n";
+        let (_, entries) = parse(PlaylistFormat::Pls, text).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.duration.is_none()));
+    }
+
+    #[test]
+    fn moved_tracks() {
+        let library = [
+            track("/music/a.mp3", Some("Alpha"), "Song", 180),
+            track("/music/b.mp3", Some("Beta"), "Song", 240),
+            track("/music/c.mp3", Some("Gamma"), "Other", 100),
+        ];
+
+        let unique = entry("/gone/x.mp3", Some("beta"), Some("song"), 241);
+        assert_eq!(match_moved(&unique, &library), Some(library[1].uuid));
+
+        // Duration tells the two apart when the artist is missing.
+        let by_duration = entry("/gone/x.mp3", None, Some("Song"), 180);
+        assert_eq!(match_moved(&by_duration, &library), Some(library[0].uuid));
+
+        let ambiguous = Entry {
+            duration: None,
+            ..entry("/gone/x.mp3", None, Some("Song"), 0)
+        };
+        assert_eq!(match_moved(&ambiguous, &library), None);
+
+        let too_long = entry("/gone/x.mp3", Some("Gamma"), Some("Other"), 110);
+        assert_eq!(match_moved(&too_long, &library), None);
+
+        let by_file_name = Entry {
+            duration: None,
+            ..entry("/gone/c.mp3", None, None, 0)
+        };
+        assert_eq!(match_moved(&by_file_name, &library), Some(library[2].uuid));
+    }
+}
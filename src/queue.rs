@@ -0,0 +1,363 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+
+/// What plays next.
+///
+/// Manually queued tracks always go first, after them playback continues
+/// through the context, i.e. the list the current track was started from.
+#[derive(Default)]
+pub struct Queue {
+    pub manual: VecDeque<Uuid>,
+    /// Context in play order, already shuffled if shuffle is on.
+    context: Vec<Uuid>,
+    /// Index of the current track in `context`.
+    pos: usize,
+    shuffle: bool,
+    repeat: Repeat,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Repeat {
+    #[default]
+    Off,
+    /// Start context over after its last track.
+    All,
+    /// Play current track again when it ends.
+    One,
+}
+
+impl Repeat {
+    pub fn next(self) -> Self {
+        match self {
+            Repeat::Off => Repeat::All,
+            Repeat::All => Repeat::One,
+            Repeat::One => Repeat::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Repeat::Off => "off",
+            Repeat::All => "all",
+            Repeat::One => "one",
+        }
+    }
+}
+
+impl FromStr for Repeat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "off" => Ok(Repeat::Off),
+            "all" => Ok(Repeat::All),
+            "one" => Ok(Repeat::One),
+            _ => Err(format!("Unknown repeat mode \"{s}\"")),
+        }
+    }
+}
+
+impl Queue {
+    /// Restores queue saved with [`Queue::context`] and [`Queue::pos`].
+    pub fn restore(
+        manual: VecDeque<Uuid>,
+        context: Vec<Uuid>,
+        pos: usize,
+        shuffle: bool,
+        repeat: Repeat,
+    ) -> Self {
+        Queue {
+            manual,
+            pos: pos.min(context.len().saturating_sub(1)),
+            context,
+            shuffle,
+            repeat,
+        }
+    }
+
+    /// Starts playing `context` from `index`, returns track to play.
+    pub fn play_from(&mut self, context: Vec<Uuid>, index: usize) -> Option<Uuid> {
+        self.context = context;
+        self.pos = index.min(self.context.len().saturating_sub(1));
+        if self.shuffle && !self.context.is_empty() {
+            // Everything except the picked track is still ahead.
+            self.context.swap(0, self.pos);
+            self.pos = 0;
+            self.shuffle_remaining();
+        }
+        self.current()
+    }
+
+    pub fn current(&self) -> Option<Uuid> {
+        self.context.get(self.pos).copied()
+    }
+
+    pub fn context(&self) -> &[Uuid] {
+        &self.context
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn push(&mut self, uuid: Uuid) {
+        self.manual.push_back(uuid);
+    }
+
+    pub fn next(&mut self) -> Option<Uuid> {
+        if let Some(uuid) = self.manual.pop_front() {
+            return Some(uuid);
+        }
+
+        if self.pos + 1 < self.context.len() {
+            self.pos += 1;
+            self.current()
+        } else if self.repeat == Repeat::All && !self.context.is_empty() {
+            self.pos = 0;
+            if self.shuffle {
+                self.context.shuffle(&mut rand::rng());
+            }
+            self.current()
+        } else {
+            None
+        }
+    }
+
+    pub fn prev(&mut self) -> Option<Uuid> {
+        self.pos = self.pos.saturating_sub(1);
+        self.current()
+    }
+
+    /// Tracks after the current one in play order, each with whether it
+    /// was queued manually.
+    pub fn upcoming(&self) -> impl Iterator<Item = (Uuid, bool)> + '_ {
+        let context = self.context.get(self.pos + 1..).unwrap_or_default();
+        self.manual
+            .iter()
+            .map(|&uuid| (uuid, true))
+            .chain(context.iter().map(|&uuid| (uuid, false)))
+    }
+
+    fn upcoming_len(&self) -> usize {
+        self.manual.len() + self.context_upcoming_len()
+    }
+
+    fn context_upcoming_len(&self) -> usize {
+        self.context.len().saturating_sub(self.pos + 1)
+    }
+
+    /// Index into `manual` or `context` of an upcoming track, `index` has to
+    /// be less than [`Queue::upcoming_len`].
+    fn upcoming_mut(&mut self, index: usize) -> &mut Uuid {
+        match index.checked_sub(self.manual.len()) {
+            None => &mut self.manual[index],
+            Some(i) => &mut self.context[self.pos + 1 + i],
+        }
+    }
+
+    /// Swaps two upcoming tracks. Manually queued tracks can trade places
+    /// with ones from the context, both parts keep their lengths.
+    pub fn swap_upcoming(&mut self, a: usize, b: usize) {
+        let len = self.upcoming_len();
+        if a >= len || b >= len {
+            return;
+        }
+        let first = *self.upcoming_mut(a);
+        let second = std::mem::replace(self.upcoming_mut(b), first);
+        *self.upcoming_mut(a) = second;
+    }
+
+    pub fn remove_upcoming(&mut self, index: usize) {
+        match index.checked_sub(self.manual.len()) {
+            None => {
+                self.manual.remove(index);
+            }
+            Some(i) if i < self.context_upcoming_len() => {
+                self.context.remove(self.pos + 1 + i);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Drops everything after the current track.
+    pub fn clear_upcoming(&mut self) {
+        self.manual.clear();
+        self.context.truncate(self.pos + 1);
+    }
+
+    /// Shuffles manually queued tracks and the rest of the context, each on
+    /// its own.
+    pub fn shuffle_upcoming(&mut self) {
+        self.manual.make_contiguous().shuffle(&mut rand::rng());
+        self.shuffle_remaining();
+    }
+
+    /// Skips upcoming tracks before `index`, returns the track at it and
+    /// whether it was queued manually.
+    pub fn skip_to(&mut self, index: usize) -> Option<(Uuid, bool)> {
+        match index.checked_sub(self.manual.len()) {
+            None => {
+                self.manual.drain(..index);
+                self.manual.pop_front().map(|uuid| (uuid, true))
+            }
+            Some(i) if i < self.context_upcoming_len() => {
+                self.manual.clear();
+                self.pos += 1 + i;
+                self.current().map(|uuid| (uuid, false))
+            }
+            Some(_) => None,
+        }
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    /// Toggling shuffle only reorders tracks after the current one, so
+    /// history stays intact. Turning it off keeps the shuffled order.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        if shuffle {
+            self.shuffle_remaining();
+        }
+    }
+
+    pub fn repeat(&self) -> Repeat {
+        self.repeat
+    }
+
+    pub fn set_repeat(&mut self, repeat: Repeat) {
+        self.repeat = repeat;
+    }
+
+    fn shuffle_remaining(&mut self) {
+        if let Some(remaining) = self.context.get_mut(self.pos + 1..) {
+            remaining.shuffle(&mut rand::rng());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two manually queued tracks and a context of five, playing the second.
+    /// Upcoming is `m[0], m[1], c[2], c[3], c[4]`.
+    fn queue() -> (Queue, Vec<Uuid>, Vec<Uuid>) {
+        let manual = (0..2).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        let context = (0..5).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        let queue = Queue::restore(
+            manual.clone().into(),
+            context.clone(),
+            1,
+            false,
+            Repeat::Off,
+        );
+        (queue, manual, context)
+    }
+
+    fn upcoming(queue: &Queue) -> Vec<(Uuid, bool)> {
+        queue.upcoming().collect()
+    }
+
+    #[test]
+    fn swap_within_and_across_parts() {
+        let (mut queue, m, c) = queue();
+
+        queue.swap_upcoming(0, 1);
+        queue.swap_upcoming(2, 4);
+        assert_eq!(
+            upcoming(&queue),
+            [
+                (m[1], true),
+                (m[0], true),
+                (c[4], false),
+                (c[3], false),
+                (c[2], false)
+            ]
+        );
+
+        // Last manual track with the last context one, lengths stay.
+        queue.swap_upcoming(1, 4);
+        assert_eq!(queue.manual, [m[1], c[2]]);
+        assert_eq!(queue.context(), [c[0], c[1], c[4], c[3], m[0]]);
+        assert_eq!(queue.current(), Some(c[1]));
+    }
+
+    #[test]
+    fn swap_out_of_range() {
+        let (mut queue, ..) = queue();
+        let before = upcoming(&queue);
+        queue.swap_upcoming(0, 5);
+        queue.swap_upcoming(5, 0);
+        queue.swap_upcoming(usize::MAX, 1);
+        assert_eq!(upcoming(&queue), before);
+    }
+
+    #[test]
+    fn remove() {
+        let (mut queue, m, c) = queue();
+
+        queue.remove_upcoming(4);
+        assert_eq!(queue.context(), [c[0], c[1], c[2], c[3]]);
+        queue.remove_upcoming(2);
+        assert_eq!(queue.context(), [c[0], c[1], c[3]]);
+        queue.remove_upcoming(1);
+        assert_eq!(queue.manual, [m[0]]);
+
+        // Past the end, nothing changes.
+        queue.remove_upcoming(2);
+        queue.remove_upcoming(usize::MAX);
+        assert_eq!(upcoming(&queue), [(m[0], true), (c[3], false)]);
+        assert_eq!(queue.current(), Some(c[1]));
+    }
+
+    #[test]
+    fn skip_to_manual() {
+        let (mut queue, m, c) = queue();
+        assert_eq!(queue.skip_to(1), Some((m[1], true)));
+        assert!(queue.manual.is_empty());
+        assert_eq!(queue.current(), Some(c[1]));
+    }
+
+    #[test]
+    fn skip_to_context() {
+        let (mut queue, _, c) = queue();
+        // First context track drops the manual ones.
+        assert_eq!(queue.skip_to(2), Some((c[2], false)));
+        assert!(queue.manual.is_empty());
+        assert_eq!(queue.pos(), 2);
+
+        // Last context track.
+        assert_eq!(queue.skip_to(1), Some((c[4], false)));
+        assert_eq!(queue.pos(), 4);
+        assert!(upcoming(&queue).is_empty());
+        assert_eq!(queue.skip_to(0), None);
+    }
+
+    #[test]
+    fn skip_out_of_range() {
+        let (mut queue, m, c) = queue();
+        assert_eq!(queue.skip_to(5), None);
+        assert_eq!(queue.skip_to(usize::MAX), None);
+        assert_eq!(queue.manual, m);
+        assert_eq!(queue.current(), Some(c[1]));
+    }
+
+    #[test]
+    fn clear() {
+        let (mut queue, _, c) = queue();
+        queue.clear_upcoming();
+        assert!(upcoming(&queue).is_empty());
+        assert_eq!(queue.context(), [c[0], c[1]]);
+        assert_eq!(queue.current(), Some(c[1]));
+
+        // Nothing after the last track either.
+        let mut at_end = Queue::restore(VecDeque::new(), c.clone(), 4, false, Repeat::Off);
+        at_end.clear_upcoming();
+        assert_eq!(at_end.context(), c);
+    }
+}
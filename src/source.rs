@@ -1,16 +1,54 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use crossbeam_channel::Sender;
 use rodio::Source;
+use rodio::source::SeekError;
 
 use crate::event::{AudioMessage, Event};
 
+/// Playback position shared between [`NotifySource`] and whoever wants to
+/// know it. Stored in microseconds.
+#[derive(Clone, Default)]
+pub struct Position {
+    pos: Arc<AtomicU64>,
+    played: Arc<AtomicU64>,
+}
+
+impl Position {
+    pub fn get(&self) -> Duration {
+        Duration::from_micros(self.pos.load(Ordering::Relaxed))
+    }
+
+    /// Length of audio pulled from the source, seeks don't move it.
+    pub fn played(&self) -> Duration {
+        Duration::from_micros(self.played.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, pos: Duration, played: Duration) {
+        self.pos.store(pos.as_micros() as u64, Ordering::Relaxed);
+        self.played
+            .store(played.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
 pub struct NotifySource<T>
 where
     T: Source,
 {
     pub inner: T,
     pub app_event_tx: Sender<Event>,
+
+    position: Position,
+    /// Position samples are counted from, moved on seeks and format changes.
+    base: Duration,
+    /// Samples played since `base`.
+    samples: u64,
+    /// Played before `base`.
+    played: Duration,
+    channels: rodio::ChannelCount,
+    sample_rate: rodio::SampleRate,
 }
 
 impl<T> NotifySource<T>
@@ -19,10 +57,47 @@ where
 {
     pub fn new(source: T, app_event_tx: Sender<Event>) -> Self {
         NotifySource {
+            channels: source.channels(),
+            sample_rate: source.sample_rate(),
             inner: source,
             app_event_tx,
+            position: Position::default(),
+            base: Duration::ZERO,
+            samples: 0,
+            played: Duration::ZERO,
         }
     }
+
+    /// Handle to the position of this source, counted from samples actually
+    /// pulled from it.
+    pub fn position(&self) -> Position {
+        self.position.clone()
+    }
+
+    /// Time played since `base`.
+    fn since_base(&self) -> Duration {
+        let per_sec = self.sample_rate as u64 * self.channels as u64;
+        if per_sec == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(self.samples as f64 / per_sec as f64)
+    }
+
+    fn update(&self) {
+        let since_base = self.since_base();
+        self.position
+            .set(self.base + since_base, self.played + since_base);
+    }
+
+    fn rebase(&mut self, base: Duration) {
+        self.played += self.since_base();
+        self.base = base;
+        self.samples = 0;
+        self.channels = self.inner.channels();
+        self.sample_rate = self.inner.sample_rate();
+        self.update();
+    }
 }
 
 impl<T> Iterator for NotifySource<T>
@@ -37,6 +112,20 @@ where
             _ = self
                 .app_event_tx
                 .send(Event::Audio(AudioMessage::EndOfTrack));
+            return n;
+        }
+
+        self.samples += 1;
+
+        // Update once per frame, format can only change on its boundary.
+        if self.samples.is_multiple_of(self.channels.max(1) as u64) {
+            self.update();
+
+            if self.inner.channels() != self.channels
+                || self.inner.sample_rate() != self.sample_rate
+            {
+                self.rebase(self.base + self.since_base());
+            }
         }
 
         n
@@ -62,4 +151,79 @@ where
     fn total_duration(&self) -> Option<Duration> {
         self.inner.total_duration()
     }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        self.rebase(pos);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::Receiver;
+    use rodio::buffer::SamplesBuffer;
+
+    use super::*;
+
+    /// One second of stereo at 1000 Hz.
+    fn source() -> (NotifySource<SamplesBuffer>, Receiver<Event>) {
+        let (event_tx, event_rx) = crossbeam_channel::unbounded();
+        let buffer = SamplesBuffer::new(2, 1000, vec![0.0; 2000]);
+        (NotifySource::new(buffer, event_tx), event_rx)
+    }
+
+    #[test]
+    fn counts_whole_frames() {
+        let (mut source, _events) = source();
+        let position = source.position();
+
+        source.by_ref().take(1000).for_each(drop);
+        assert_eq!(position.get(), Duration::from_millis(500));
+
+        // Left sample of the next frame alone doesn't move it.
+        source.next();
+        assert_eq!(position.get(), Duration::from_millis(500));
+        source.next();
+        assert_eq!(position.get(), Duration::from_millis(501));
+    }
+
+    #[test]
+    fn counts_from_seek_target() {
+        let (mut source, _events) = source();
+        let position = source.position();
+
+        source.by_ref().take(500).for_each(drop);
+        assert_eq!(position.get(), Duration::from_millis(250));
+
+        source.try_seek(Duration::from_millis(750)).unwrap();
+        assert_eq!(position.get(), Duration::from_millis(750));
+        assert_eq!(position.played(), Duration::from_millis(250));
+
+        source.by_ref().take(100).for_each(drop);
+        assert_eq!(position.get(), Duration::from_millis(800));
+        assert_eq!(position.played(), Duration::from_millis(300));
+
+        // Backwards too.
+        source.try_seek(Duration::from_millis(100)).unwrap();
+        source.by_ref().take(100).for_each(drop);
+        assert_eq!(position.get(), Duration::from_millis(150));
+        assert_eq!(position.played(), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn reports_end_of_track() {
+        let (mut source, events) = source();
+        let position = source.position();
+
+        source.by_ref().take(1998).for_each(drop);
+        assert!(events.try_recv().is_err());
+
+        assert_eq!(source.by_ref().count(), 2);
+        assert_eq!(position.get(), Duration::from_secs(1));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(Event::Audio(AudioMessage::EndOfTrack))
+        ));
+    }
 }
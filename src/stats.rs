@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use uuid::Uuid;
+
+use crate::db::Play;
+use crate::models::Track;
+
+const TOP_LEN: usize = 10;
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Period {
+    Week,
+    #[default]
+    Month,
+    Year,
+    AllTime,
+}
+
+impl Period {
+    pub fn next(self) -> Self {
+        match self {
+            Period::Week => Period::Month,
+            Period::Month => Period::Year,
+            Period::Year => Period::AllTime,
+            Period::AllTime => Period::Week,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Year => "year",
+            Period::AllTime => "all time",
+        }
+    }
+
+    /// Start of the period counting back from now.
+    pub fn since(self) -> Option<SystemTime> {
+        let days = match self {
+            Period::Week => 7,
+            Period::Month => 30,
+            Period::Year => 365,
+            Period::AllTime => return None,
+        };
+
+        SystemTime::now().checked_sub(Duration::from_secs(days * DAY))
+    }
+}
+
+/// Row of a top list.
+pub struct TopEntry {
+    pub name: String,
+    pub count: u32,
+    /// Set in lists of tracks.
+    pub track: Option<Uuid>,
+}
+
+/// Aggregated play history, names are resolved against the library.
+#[derive(Default)]
+pub struct ListeningStats {
+    pub top_tracks: Vec<TopEntry>,
+    pub top_artists: Vec<TopEntry>,
+    pub top_albums: Vec<TopEntry>,
+    pub most_skipped: Vec<TopEntry>,
+    pub total_listened: Duration,
+    /// Listening time keyed by local days since unix epoch, see [`day`].
+    pub per_day: HashMap<u64, Duration>,
+}
+
+impl ListeningStats {
+    pub fn compute(history: &[Play], library: &[Track]) -> Self {
+        let tracks: HashMap<Uuid, &Track> = library.iter().map(|t| (t.uuid, t)).collect();
+
+        let mut plays: HashMap<Uuid, u32> = HashMap::new();
+        let mut skips: HashMap<Uuid, u32> = HashMap::new();
+        let mut artists: HashMap<String, u32> = HashMap::new();
+        let mut albums: HashMap<String, u32> = HashMap::new();
+        let mut stats = ListeningStats::default();
+
+        for play in history {
+            stats.total_listened += play.listened;
+            *stats.per_day.entry(day(play.started_at)).or_default() += play.listened;
+
+            if play.skipped {
+                *skips.entry(play.track).or_default() += 1;
+                continue;
+            }
+
+            *plays.entry(play.track).or_default() += 1;
+            if let Some(track) = tracks.get(&play.track) {
+                if let Some(artist) = track.tags.artist.as_ref() {
+                    *artists.entry(artist.clone()).or_default() += 1;
+                }
+                if let Some(album) = track.tags.album.as_ref() {
+                    *albums.entry(album.clone()).or_default() += 1;
+                }
+            }
+        }
+
+        let track_entry = |(uuid, count): (Uuid, u32)| TopEntry {
+            name: tracks
+                .get(&uuid)
+                .map(|t| t.name())
+                .unwrap_or_else(|| "Unknown track".to_string()),
+            count,
+            track: Some(uuid),
+        };
+        let entry = |(name, count): (String, u32)| TopEntry {
+            name,
+            count,
+            track: None,
+        };
+
+        stats.top_tracks = top(plays.into_iter().map(track_entry));
+        stats.most_skipped = top(skips.into_iter().map(track_entry));
+        stats.top_artists = top(artists.into_iter().map(entry));
+        stats.top_albums = top(albums.into_iter().map(entry));
+
+        stats
+    }
+}
+
+/// Days since unix epoch in local time, so a day starts at local midnight
+/// rather than UTC midnight.
+pub fn day(time: SystemTime) -> u64 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    local_day(secs, utc_offset(secs))
+}
+
+fn local_day(secs: i64, utc_offset: i64) -> u64 {
+    (secs + utc_offset).max(0) as u64 / DAY
+}
+
+/// Seconds local time is ahead of UTC at `secs` since unix epoch, with
+/// daylight saving time of that moment.
+#[cfg(unix)]
+fn utc_offset(secs: i64) -> i64 {
+    let time = secs as libc::time_t;
+    // SAFETY: `tm` is plain data that `localtime_r` fills in, both pointers
+    // are valid for the call and the reentrant variant keeps no state.
+    unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+#[cfg(not(unix))]
+fn utc_offset(_secs: i64) -> i64 {
+    0
+}
+
+fn top(entries: impl Iterator<Item = TopEntry>) -> Vec<TopEntry> {
+    let mut counts: Vec<_> = entries.collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts.truncate(TOP_LEN);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::models::{PlayContext, TrackStats, TrackTags};
+
+    fn track(title: &str, artist: &str, album: &str) -> Track {
+        Track {
+            uuid: Uuid::new_v4(),
+            duration: Duration::ZERO,
+            path: PathBuf::from(format!("/music/{title}.mp3")),
+            tags: TrackTags {
+                title: Some(title.to_string()),
+                artist: Some(artist.to_string()),
+                album: Some(album.to_string()),
+                ..Default::default()
+            },
+            stats: TrackStats::default(),
+            rating: 0,
+            favorite: false,
+        }
+    }
+
+    fn play(track: &Track, started_at: SystemTime, skipped: bool) -> Play {
+        Play {
+            track: track.uuid,
+            started_at,
+            listened: Duration::from_secs(60),
+            skipped,
+            context: PlayContext::Library,
+        }
+    }
+
+    fn names(entries: &[TopEntry]) -> Vec<(&str, u32)> {
+        entries.iter().map(|e| (e.name.as_str(), e.count)).collect()
+    }
+
+    #[test]
+    fn top_lists_and_skips() {
+        let library = [
+            track("One", "Alpha", "First"),
+            track("Two", "Alpha", "Second"),
+            track("Three", "Beta", "Second"),
+        ];
+        let now = SystemTime::now();
+        let history = [
+            play(&library[0], now, false),
+            play(&library[1], now, false),
+            play(&library[1], now, false),
+            play(&library[2], now, true),
+            play(&library[2], now, true),
+            play(&library[0], now, true),
+        ];
+
+        let stats = ListeningStats::compute(&history, &library);
+        assert_eq!(names(&stats.top_tracks), [("Two", 2), ("One", 1)]);
+        assert_eq!(names(&stats.top_artists), [("Alpha", 3)]);
+        assert_eq!(names(&stats.top_albums), [("Second", 2), ("First", 1)]);
+        assert_eq!(names(&stats.most_skipped), [("Three", 2), ("One", 1)]);
+        assert_eq!(stats.top_tracks[0].track, Some(library[1].uuid));
+        assert_eq!(stats.top_artists[0].track, None);
+    }
+
+    #[test]
+    fn top_lists_are_cut_and_ties_sorted_by_name() {
+        let library = (0..TOP_LEN + 2)
+            .map(|i| track(&format!("{i:02}"), "Artist", "Album"))
+            .collect::<Vec<_>>();
+        let history = library
+            .iter()
+            .rev()
+            .map(|t| play(t, SystemTime::now(), false))
+            .collect::<Vec<_>>();
+
+        let stats = ListeningStats::compute(&history, &library);
+        assert_eq!(stats.top_tracks.len(), TOP_LEN);
+        assert_eq!(stats.top_tracks[0].name, "00");
+        assert_eq!(
+            stats.top_tracks[TOP_LEN - 1].name,
+            format!("{:02}", TOP_LEN - 1)
+        );
+    }
+
+    #[test]
+    fn totals_per_day() {
+        let library = [track("One", "Alpha", "First")];
+        let today = SystemTime::now();
+        let yesterday = today - Duration::from_secs(DAY);
+        let history = [
+            play(&library[0], today, false),
+            // Skipped plays were still listened to for a while.
+            play(&library[0], today, true),
+            play(&library[0], yesterday, false),
+        ];
+
+        let stats = ListeningStats::compute(&history, &library);
+        assert_eq!(stats.total_listened, Duration::from_secs(180));
+        assert_eq!(stats.per_day[&day(today)], Duration::from_secs(120));
+        assert_eq!(stats.per_day[&day(yesterday)], Duration::from_secs(60));
+    }
+
+    #[test]
+    fn days_start_at_local_midnight() {
+        let hour = 60 * 60;
+        // 1970-01-02 23:30 and 00:30 UTC.
+        let late = (DAY + 23 * hour + hour / 2) as i64;
+        let early = (DAY + hour / 2) as i64;
+
+        assert_eq!(local_day(late, 0), 1);
+        assert_eq!(local_day(late, hour as i64), 2);
+        assert_eq!(local_day(early, 0), 1);
+        assert_eq!(local_day(early, -5 * hour as i64), 0);
+    }
+}
@@ -1,3 +1,6 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use color_eyre::Result;
@@ -6,17 +9,38 @@ use crossterm::event;
 
 use crate::event::{Event, Key};
 
-pub fn spawn_event_emmiter(
-    event_tx: Sender<Event>,
-    tickrate: Duration,
-) -> Result<()> {
+/// Tick interval shared with the event emitter so it can be changed while
+/// running, e.g. slowed down while the terminal is unfocused.
+#[derive(Clone)]
+pub struct TickRate(Arc<AtomicU64>);
+
+impl TickRate {
+    pub fn new(tickrate: Duration) -> Self {
+        TickRate(Arc::new(AtomicU64::new(tickrate.as_millis() as u64)))
+    }
+
+    pub fn get(&self) -> Duration {
+        Duration::from_millis(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, tickrate: Duration) {
+        self.0.store(tickrate.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+pub fn spawn_event_emmiter(event_tx: Sender<Event>, tickrate: TickRate) -> Result<()> {
     _ = std::thread::spawn(move || -> Result<()> {
         loop {
-            if event::poll(tickrate)? {
-                if let event::Event::Key(key) = event::read()? {
-                    let key = Key::from(key);
-                    event_tx.send(Event::Input(key))?;
-                }
+            if event::poll(tickrate.get())? {
+                let event = match event::read()? {
+                    event::Event::Key(key) => Event::Input(Key::from(key)),
+                    event::Event::Mouse(mouse) => Event::Mouse(mouse),
+                    event::Event::Resize(_, _) => Event::Resize,
+                    event::Event::FocusGained => Event::FocusGained,
+                    event::Event::FocusLost => Event::FocusLost,
+                    event::Event::Paste(_) => continue,
+                };
+                event_tx.send(event)?;
             }
 
             event_tx.send(Event::Tick)?;
@@ -25,3 +49,115 @@ pub fn spawn_event_emmiter(
 
     Ok(())
 }
+
+/// Same file under any spelling of its path, as far as the file system
+/// can tell.
+pub fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Ticks without a terminal, for headless mode.
+pub fn spawn_ticker(event_tx: Sender<Event>, tickrate: Duration) {
+    _ = std::thread::spawn(move || {
+        while event_tx.send(Event::Tick).is_ok() {
+            std::thread::sleep(tickrate);
+        }
+    });
+}
+
+/// Sends lines read from stdin until it's closed, then [`Event::LinesEnd`].
+pub fn spawn_stdin_reader(event_tx: Sender<Event>) {
+    _ = std::thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(|l| l.ok()) {
+            if event_tx.send(Event::Line(line)).is_err() {
+                return;
+            }
+        }
+        _ = event_tx.send(Event::LinesEnd);
+    });
+}
+
+/// Human readable duration like `3h 25m`, for totals rather than
+/// timestamps.
+pub fn format_duration(duration: Duration) -> String {
+    let mins = duration.as_secs() / 60;
+    match (mins / 60, mins % 60) {
+        (0, mins) => format!("{mins}m"),
+        (hours, mins) => format!("{hours}h {mins}m"),
+    }
+}
+
+/// Playback position like `3:07` or `1:02:09`.
+pub fn format_position(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, mins, secs) => format!("{mins}:{secs:02}"),
+        (hours, mins, secs) => format!("{hours}:{mins:02}:{secs:02}"),
+    }
+}
+
+/// Scores how well `text` matches `pattern` typed by the user, `None` if
+/// it doesn't contain pattern's characters in order. Case insensitive,
+/// consecutive characters and word starts score higher.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut text = text.chars().flat_map(char::to_lowercase).enumerate();
+    let mut prev_char = None;
+    let mut last_match = None;
+
+    for p in pattern.chars().flat_map(char::to_lowercase) {
+        if p == ' ' {
+            continue;
+        }
+        loop {
+            let (i, c) = text.next()?;
+            let word_start = prev_char.is_none_or(|c: char| !c.is_alphanumeric());
+            prev_char = Some(c);
+            if c != p {
+                // Gaps cost a little so tighter matches win.
+                score -= 1;
+                continue;
+            }
+
+            score += 1;
+            if last_match.is_some_and(|j| j + 1 == i) {
+                score += 4;
+            }
+            if word_start {
+                score += 3;
+            }
+            last_match = Some(i);
+            break;
+        }
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_in_order() {
+        assert!(fuzzy_score("bhs", "Black Hole Sun").is_some());
+        assert!(fuzzy_score("BLACK", "black hole sun").is_some());
+        assert!(fuzzy_score("black sun", "Black Hole Sun").is_some());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+
+        assert_eq!(fuzzy_score("shb", "Black Hole Sun"), None);
+        assert_eq!(fuzzy_score("blackk", "Black Hole Sun"), None);
+        assert_eq!(fuzzy_score("a", ""), None);
+    }
+
+    #[test]
+    fn fuzzy_prefers_tight_matches() {
+        let score = |text| fuzzy_score("sun", text).unwrap();
+        // Consecutive beats scattered.
+        assert!(score("Sunday") > score("Sour Unknown"));
+        // Word starts beat the middle of words.
+        assert!(score("A Sun") > score("Xasun"));
+        // Fewer skipped characters win.
+        assert!(score("So Unknown") > score("Solo Unknown"));
+    }
+}